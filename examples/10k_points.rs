@@ -35,7 +35,6 @@ const HEIGHT: u32 = 800;
 
 fn setup() {
     size(WIDTH, HEIGHT);
-    background(0.2);
 
     let mut ps: Vec<Point3<f32>> = Vec::with_capacity(N_OBJECTS);
     for _ in 0..N_OBJECTS {
@@ -51,8 +50,9 @@ fn setup() {
 }
 
 fn draw() {
+    background(0.2);
     stroke((1.0, 1.0, 1.0, 0.3));
-    stroke_weight(12);
+    stroke_weight(12.0);
     unsafe {
         if let Some(ref ps) = POINTS {
             for p in ps {
@@ -33,11 +33,11 @@ const HEIGHT: u32 = 400;
 
 fn setup() {
     size(WIDTH, HEIGHT);
-    background(0.2);
 }
 
 const RADIUS: f32 = 100.0;
 fn draw() {
+    background(0.2);
     let radius_offset: Vector3<f32> = Vector3::new(RADIUS, 0.0, 0.0);
 
     let p1: Point3<f32> = Point3::new(-50.0, -50.0, 0.0);
@@ -46,6 +46,9 @@ fn draw() {
     let tl: Point3<f32> = Point3::new(-50.0, 50.0, 0.0);
     let br: Point3<f32> = Point3::new(50.0, -50.0, 0.0);
 
+    stroke_weight(2.0);
+    stroke(1.0);
+
     fill((1.0, 0.0, 0.0));
     push_matrix();
     let noise_val;
@@ -81,7 +84,7 @@ fn draw() {
     rect(tl, br);
     pop_matrix();
 
-    stroke_weight(10);
+    stroke_weight(10.0);
     stroke((1.0, 1.0, 0.0));
 
     push_matrix();
@@ -0,0 +1,79 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+extern crate p5;
+
+use p5::*;
+
+static mut T: f32 = 0.0;
+
+const WIDTH: u32 = 600;
+const HEIGHT: u32 = 400;
+
+fn setup() {
+    size_with_renderer(WIDTH, HEIGHT, Renderer::P3D);
+}
+
+fn draw() {
+    background(0.2);
+    let t;
+    unsafe {
+        t = T;
+        T += 0.01;
+    }
+
+    lights();
+    light_specular(1.0);
+    point_light((0.6, 0.3, 0.1), Point3::new(300.0, 200.0, 200.0));
+
+    no_stroke();
+    fill((0.8, 0.8, 0.8));
+    specular_material(0.5);
+    shininess(20.0);
+
+    let shapes: [&Fn(); 6] = [
+        &|| box_(60.0, 60.0, 60.0),
+        &|| sphere(40.0),
+        &|| plane(70.0, 70.0),
+        &|| cylinder(30.0, 70.0),
+        &|| cone(30.0, 70.0),
+        &|| torus(30.0, 12.0),
+    ];
+    for (i, shape) in shapes.iter().enumerate() {
+        push_matrix();
+        rotate_x(t);
+        rotate_y(1.3 * t);
+        translate(&Vector3::new(
+            (i % 3) as f32 * 180.0 - 180.0,
+            100.0 - (i / 3) as f32 * 200.0,
+            0.0,
+        ));
+        shape();
+        pop_matrix();
+    }
+}
+
+fn main() {
+    run_sketch(setup, draw, true);
+}
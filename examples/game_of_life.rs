@@ -0,0 +1,92 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+extern crate p5;
+
+use p5::*;
+
+const WIDTH: u32 = 512;
+const HEIGHT: u32 = 512;
+
+// Conway's game of life, with the cells in the red channel. The first step
+// seeds the state at random.
+const LIFE_SHADER: &'static str = "#version 330 core
+uniform sampler2D state;
+uniform vec2 texel_size;
+uniform int step_count;
+in vec2 tex_coord;
+out vec4 frag_color;
+
+float alive(vec2 offset) {
+    return texture(state, tex_coord + offset * texel_size).r;
+}
+
+void main() {
+    if (step_count == 0) {
+        float seed = fract(sin(dot(tex_coord, vec2(12.9898, 78.233))) * 43758.5453);
+        frag_color = vec4(step(0.7, seed), 0.0, 0.0, 1.0);
+        return;
+    }
+    float neighbours = 0.0;
+    for (int y = -1; y <= 1; y++) {
+        for (int x = -1; x <= 1; x++) {
+            if (x != 0 || y != 0) {
+                neighbours += alive(vec2(x, y));
+            }
+        }
+    }
+    float cell = alive(vec2(0.0));
+    float next = neighbours == 3.0 || (cell > 0.5 && neighbours == 2.0) ? 1.0 : 0.0;
+    frag_color = vec4(next, 0.0, 0.0, 1.0);
+}
+";
+
+static mut LIFE: Option<(FeedbackBuffer, FilterShader)> = None;
+
+fn setup() {
+    size(WIDTH, HEIGHT);
+    unsafe {
+        LIFE = Some((
+            FeedbackBuffer::new(WIDTH / 4, HEIGHT / 4),
+            FilterShader::new(LIFE_SHADER),
+        ));
+    }
+}
+
+fn draw() {
+    background(0.1);
+    if let Some(&(ref buffer, ref shader)) = unsafe { LIFE.as_ref() } {
+        buffer.step(shader);
+        no_stroke();
+        fill_feedback(buffer);
+        rect(
+            Point3::new(-0.5 * WIDTH as f32, 0.5 * HEIGHT as f32, 0.0),
+            Point3::new(0.5 * WIDTH as f32, -0.5 * HEIGHT as f32, 0.0),
+        );
+    }
+}
+
+fn main() {
+    run_sketch(setup, draw, true);
+}
@@ -44,7 +44,6 @@ static mut T: f32 = 0.0;
 
 fn setup() {
     size(WIDTH, HEIGHT);
-    background(0.2);
 
     let mut points: Vec<Point3<f32>> = vec![];
     for _ in 0..N_POINTS {
@@ -60,11 +59,12 @@ fn setup() {
 const RADIUS: f32 = 1.0;
 
 fn draw() {
+    background(0.2);
     stroke((1.0, 1.0, 1.0, 0.3));
-    stroke_weight(1);
+    stroke_weight(1.0);
 
     // 1D - 0.01
-    // stroke_weight(2);
+    // stroke_weight(2.0);
     // const SCALE: f32 = 0.03;
     // for x in 0..WIDTH {
     //     let y = HEIGHT as f32 * noise(Point4::new(
@@ -81,7 +81,7 @@ fn draw() {
     // }
 
     // 2D
-    // stroke_weight(1);
+    // stroke_weight(1.0);
     // const SCALE: f32 = 0.01;
     // for y in 0..HEIGHT {
     //     for x in 0..WIDTH {
@@ -105,7 +105,7 @@ fn draw() {
     //     t = T;
     //     T += 1.0 / 75.0;
     // }
-    // stroke_weight(1);
+    // stroke_weight(1.0);
     // const SCALE: f32 = 0.01;
     // for y in 0..HEIGHT {
     //     for x in 0..WIDTH {
@@ -124,7 +124,7 @@ fn draw() {
     // }
 
     // 2D with perlin and thresholding
-    // stroke_weight(4);
+    // stroke_weight(4.0);
     // const SCALE: f32 = 0.05;
     // for y in 0..HEIGHT/4 {
     //     for x in 0..WIDTH/4 {
@@ -152,7 +152,7 @@ fn draw() {
     //     t = T;
     //     T += 1.0 / 300.0;
     // }
-    // stroke_weight(4);
+    // stroke_weight(4.0);
     // const SCALE: f32 = 0.04;
     // let angle = 2.0 * f32::consts::PI * t;
     // let rcos = RADIUS * angle.cos();
@@ -183,12 +183,12 @@ fn draw() {
     //     t = T;
     //     T += 1.0 / 300.0;
     // }
-    // stroke_weight(1);
+    // stroke_weight(1.0);
     // const SCALE: f32 = 0.01;
     // let angle = 2.0 * f32::consts::PI * t;
     // let rcos = RADIUS * angle.cos();
     // let rsin = RADIUS * angle.sin();
-    // stroke_weight(4);
+    // stroke_weight(4.0);
     // for x in 0..WIDTH {
     //     let n = 2.0 * (noise(Point4::new(
     //         x as f32 * SCALE,
@@ -208,7 +208,7 @@ fn draw() {
     //     t = T;
     //     T += 1.0 / 300.0;
     // }
-    // stroke_weight(4);
+    // stroke_weight(4.0);
     // const SCALE: f32 = 0.05;
     // let angle = 2.0 * f32::consts::PI * t;
     // let rcos = RADIUS * angle.cos();
@@ -234,7 +234,7 @@ fn draw() {
         t = T;
         T += 1.0 / 300.0;
     }
-    stroke_weight(2);
+    stroke_weight(2.0);
     const SCALE: f32 = 0.005;
     let angle = 2.0 * f32::consts::PI * t;
     let rcos = RADIUS * angle.cos();
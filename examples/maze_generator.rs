@@ -58,7 +58,11 @@ impl Cell {
         self.walls[wall as usize] = false;
     }
     fn show(&self) {
+        // only the remaining walls are stroked, not the whole cell outline
+        let wall_color = get_stroke();
+        no_stroke();
         rect(self.corners[0], self.corners[2]);
+        stroke(wall_color);
         for i in 0..self.walls.len() {
             if self.walls[i] {
                 line(self.corners[i], self.corners[(i + 1) % self.corners.len()]);
@@ -261,7 +265,6 @@ fn cell_to_point(i: i32, j: i32) -> Point3<f32> {
 
 fn setup() {
     size(800, 800);
-    background(0.2);
     unsafe {
         GRID = Some(Grid::new(COLS, ROWS, CELL_WIDTH, CELL_HEIGHT));
         if let Some(ref mut grid) = GRID {
@@ -271,7 +274,8 @@ fn setup() {
 }
 
 fn draw() {
-    stroke_weight(1);
+    background(0.2);
+    stroke_weight(1.0);
     stroke(1.0);
     unsafe {
         if let Some(ref mut grid) = GRID {
@@ -0,0 +1,75 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+extern crate p5;
+
+use p5::*;
+
+use std::env;
+
+static mut T: f32 = 0.0;
+static mut MODEL: Option<Model> = None;
+
+fn setup() {
+    size_with_renderer(600, 400, Renderer::P3D);
+    // load the OBJ file given on the command line or a small default
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "examples/data/octahedron.obj".to_string());
+    match load_model(&path, true) {
+        Ok(m) => unsafe { MODEL = Some(m) },
+        Err(e) => println!("Could not load {}: {}", path, e),
+    }
+}
+
+fn draw() {
+    background(0.2);
+    let t;
+    unsafe {
+        t = T;
+        T += 0.01;
+    }
+
+    lights();
+    light_specular(1.0);
+    point_light((0.6, 0.6, 0.6), Point3::new(300.0, 200.0, 200.0));
+
+    no_stroke();
+    fill((0.8, 0.8, 0.8));
+
+    push_matrix();
+    rotate_x(t);
+    rotate_y(1.3 * t);
+    scale(&Vector3::new(200.0, 200.0, 200.0));
+    unsafe {
+        if let Some(ref m) = MODEL {
+            model(m);
+        }
+    }
+    pop_matrix();
+}
+
+fn main() {
+    run_sketch(setup, draw, true);
+}
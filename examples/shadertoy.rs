@@ -0,0 +1,48 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+extern crate p5;
+
+use p5::*;
+
+// Plasma that leaves fading trails behind the mouse
+const FRAGMENT_SHADER: &'static str = "
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    vec2 uv = fragCoord / iResolution.xy;
+    float v = sin(10.0 * uv.x + iTime) + sin(10.0 * uv.y + 1.3 * iTime)
+        + sin(10.0 * length(uv - 0.5) - 2.0 * iTime);
+    vec3 plasma = 0.5 + 0.5 * cos(v + vec3(0.0, 2.0, 4.0));
+    vec3 previous = texture(iChannel0, uv).rgb;
+    float brush = iMouse.z > 0.0 ? step(length(fragCoord - iMouse.xy), 20.0) : 0.0;
+    fragColor = vec4(max(0.5 * plasma, mix(0.97 * previous, vec3(1.0), brush)), 1.0);
+}
+";
+
+fn setup() {
+    size(600, 400);
+}
+
+fn main() {
+    run_shader_sketch(setup, FRAGMENT_SHADER, true);
+}
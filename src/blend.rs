@@ -0,0 +1,53 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use sketch::SKETCH;
+
+// How shapes are combined with what is already drawn, as with Processing's
+// blendMode()
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlendMode {
+    // mixed by the shape's alpha, the default
+    Blend,
+    // the shape's colour times its alpha is added, so overlaps glow
+    Add,
+    // the shape's colour times its alpha is subtracted
+    Subtract,
+    // the colours are multiplied, which only ever darkens
+    Multiply,
+    // the inverted colours are multiplied, which only ever lightens
+    Screen,
+    // the larger of each component
+    Lightest,
+    // the smaller of each component
+    Darkest,
+    // like Difference but with less contrast
+    Exclusion,
+    // the shape's colour replaces what is drawn, alpha and all
+    Replace,
+}
+
+pub fn blend_mode(mode: BlendMode) {
+    SKETCH.lock().unwrap().blend_mode = mode;
+}
@@ -0,0 +1,158 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use sketch::SKETCH;
+
+use na::{Matrix4, Point3, Transform3, Vector3};
+
+use std::f32::consts::*;
+
+// The default field of view, as in Processing
+const FIELD_OF_VIEW: f32 = PI / 3.0;
+
+// The distance from the default eye position to the z = 0 plane, at which the
+// default perspective shows exactly width x height pixels
+fn eye_distance(height: u32) -> f32 {
+    0.5 * height as f32 / (0.5 * FIELD_OF_VIEW).tan()
+}
+
+// The view transformation looking at the origin from eye_distance() along +z
+pub fn default_view(height: u32) -> Transform3<f32> {
+    look_at(
+        &Point3::new(0.0, 0.0, eye_distance(height)),
+        &Point3::origin(),
+        &Vector3::y(),
+    )
+}
+
+// Processing's default perspective projection for the window size
+pub fn default_projection(width: u32, height: u32) -> Transform3<f32> {
+    let eye_distance = eye_distance(height);
+    perspective_projection(
+        FIELD_OF_VIEW,
+        width as f32 / height as f32,
+        0.1 * eye_distance,
+        10.0 * eye_distance,
+    )
+}
+
+fn look_at(eye: &Point3<f32>, center: &Point3<f32>, up: &Vector3<f32>) -> Transform3<f32> {
+    Transform3::from_matrix_unchecked(Matrix4::look_at_rh(eye, center, up))
+}
+
+fn perspective_projection(fovy: f32, aspect: f32, near: f32, far: f32) -> Transform3<f32> {
+    let top = near * (0.5 * fovy).tan();
+    let right = top * aspect;
+    frustum_projection(-right, right, -top, top, near, far)
+}
+
+fn ortho_projection(
+    left: f32,
+    right: f32,
+    bottom: f32,
+    top: f32,
+    near: f32,
+    far: f32,
+) -> Transform3<f32> {
+    Transform3::from_matrix_unchecked(Matrix4::new_orthographic(
+        left, right, bottom, top, near, far,
+    ))
+}
+
+fn frustum_projection(
+    left: f32,
+    right: f32,
+    bottom: f32,
+    top: f32,
+    near: f32,
+    far: f32,
+) -> Transform3<f32> {
+    Transform3::from_matrix_unchecked(Matrix4::new(
+        2.0 * near / (right - left),
+        0.0,
+        (right + left) / (right - left),
+        0.0,
+        0.0,
+        2.0 * near / (top - bottom),
+        (top + bottom) / (top - bottom),
+        0.0,
+        0.0,
+        0.0,
+        -(far + near) / (far - near),
+        -2.0 * far * near / (far - near),
+        0.0,
+        0.0,
+        -1.0,
+        0.0,
+    ))
+}
+
+// Places the eye at a point looking towards the center, with up pointing
+// towards the top of the window
+pub fn camera(eye: Point3<f32>, center: Point3<f32>, up: Vector3<f32>) {
+    SKETCH.lock().unwrap().transformation = look_at(&eye, &center, &up);
+}
+
+// Restores the camera set by size()
+pub fn default_camera() {
+    let mut sketch = SKETCH.lock().unwrap();
+    sketch.transformation = default_view(sketch.height);
+}
+
+// A perspective projection with the vertical field of view in radians, the
+// aspect ratio of width to height and the distances to the near and far
+// clipping planes in front of the eye
+pub fn perspective(fovy: f32, aspect: f32, near: f32, far: f32) {
+    SKETCH.lock().unwrap().projection = perspective_projection(fovy, aspect, near, far);
+}
+
+// Restores the perspective projection set by size()
+pub fn default_perspective() {
+    let mut sketch = SKETCH.lock().unwrap();
+    sketch.projection = default_projection(sketch.width, sketch.height);
+}
+
+// An orthographic projection of the box between the left, right, bottom and
+// top clipping planes and the near and far ones in front of the eye
+pub fn ortho(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) {
+    SKETCH.lock().unwrap().projection = ortho_projection(left, right, bottom, top, near, far);
+}
+
+// An orthographic projection of the whole window where one unit is one pixel
+pub fn default_ortho() {
+    let mut sketch = SKETCH.lock().unwrap();
+    let half_width = 0.5 * sketch.width as f32;
+    let half_height = 0.5 * sketch.height as f32;
+    let far = 10.0 * eye_distance(sketch.height);
+    sketch.projection =
+        ortho_projection(-half_width, half_width, -half_height, half_height, 0.0, far);
+}
+
+// A perspective projection of the part of the pyramid from the eye through the
+// rectangle between the left, right, bottom and top coordinates on the near
+// clipping plane, up to the far clipping plane
+pub fn frustum(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) {
+    SKETCH.lock().unwrap().projection =
+        frustum_projection(left, right, bottom, top, near, far);
+}
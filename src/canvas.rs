@@ -0,0 +1,248 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use gl;
+use gl::types::*;
+
+use std::ptr;
+
+// An offscreen framebuffer that shapes are drawn into, multisampled for
+// smooth() and resolved into the window's framebuffer when the frame is done.
+// Canvases must only be used on the thread that owns the GL context.
+pub struct Canvas {
+    framebuffer: GLuint,
+    color_buffer: GLuint,
+    depth_buffer: GLuint,
+    pub width: u32,
+    pub height: u32,
+    // the samples per pixel, or 0 if not multisampled
+    pub samples: u32,
+}
+
+impl Canvas {
+    // Creates a canvas with as many of the samples as the GL supports, or
+    // without multisampling if it supports none
+    pub fn new(width: u32, height: u32, samples: u32) -> Canvas {
+        let mut max_samples: GLint = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_SAMPLES, &mut max_samples);
+        }
+        let samples = samples.min(max_samples.max(0) as u32);
+        let canvas = Canvas::with_samples(width, height, samples);
+        if samples > 0 && !canvas.is_complete() {
+            return Canvas::with_samples(width, height, 0);
+        }
+        canvas
+    }
+
+    fn with_samples(width: u32, height: u32, samples: u32) -> Canvas {
+        let mut canvas = Canvas {
+            framebuffer: 0,
+            color_buffer: 0,
+            depth_buffer: 0,
+            width,
+            height,
+            samples,
+        };
+        unsafe {
+            gl::GenFramebuffers(1, &mut canvas.framebuffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, canvas.framebuffer);
+            gl::GenRenderbuffers(1, &mut canvas.color_buffer);
+            gl::GenRenderbuffers(1, &mut canvas.depth_buffer);
+            let attachments = [
+                (canvas.color_buffer, gl::RGBA8, gl::COLOR_ATTACHMENT0),
+                (
+                    canvas.depth_buffer,
+                    gl::DEPTH_COMPONENT24,
+                    gl::DEPTH_ATTACHMENT,
+                ),
+            ];
+            for &(renderbuffer, format, attachment) in &attachments {
+                gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+                gl::RenderbufferStorageMultisample(
+                    gl::RENDERBUFFER,
+                    samples as GLsizei,
+                    format,
+                    width as GLsizei,
+                    height as GLsizei,
+                );
+                gl::FramebufferRenderbuffer(
+                    gl::FRAMEBUFFER,
+                    attachment,
+                    gl::RENDERBUFFER,
+                    renderbuffer,
+                );
+            }
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+        }
+        canvas.bind();
+        canvas
+    }
+
+    fn is_complete(&self) -> bool {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+            gl::CheckFramebufferStatus(gl::FRAMEBUFFER) == gl::FRAMEBUFFER_COMPLETE
+        }
+    }
+
+    // Directs drawing into the canvas
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+        }
+    }
+
+    // Copies the canvas into the render texture, which must be the same size,
+    // resolving the samples, and directs drawing back into the canvas
+    pub fn resolve_into(&self, target: &RenderTexture) {
+        let (w, h) = (self.width as GLint, self.height as GLint);
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.framebuffer);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target.framebuffer);
+            gl::BlitFramebuffer(0, 0, w, h, 0, 0, w, h, gl::COLOR_BUFFER_BIT, gl::NEAREST);
+        }
+        self.bind();
+    }
+
+    // Copies the canvas into the bottom-left of the window's framebuffer,
+    // resolving the samples, and directs drawing back into the canvas
+    pub fn blit_to_window(&self) {
+        let (w, h) = (self.width as GLint, self.height as GLint);
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.framebuffer);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+            gl::BlitFramebuffer(0, 0, w, h, 0, 0, w, h, gl::COLOR_BUFFER_BIT, gl::NEAREST);
+        }
+        self.bind();
+    }
+}
+
+impl Drop for Canvas {
+    fn drop(&mut self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::DeleteFramebuffers(1, &self.framebuffer);
+            gl::DeleteRenderbuffers(1, &self.color_buffer);
+            gl::DeleteRenderbuffers(1, &self.depth_buffer);
+        }
+    }
+}
+
+// A framebuffer that draws into a texture, for passes such as filter() that
+// read back what was drawn
+pub struct RenderTexture {
+    framebuffer: GLuint,
+    pub texture: GLuint,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl RenderTexture {
+    pub fn new(width: u32, height: u32) -> RenderTexture {
+        RenderTexture::with_format(
+            width,
+            height,
+            gl::RGBA8,
+            gl::UNSIGNED_BYTE,
+            gl::CLAMP_TO_EDGE,
+            gl::LINEAR,
+        )
+    }
+
+    // Creates a render texture with the internal format, the type of the
+    // components of that format, how texture coordinates outside 0 to 1 wrap
+    // and how texels are filtered when sampled. Its contents are undefined
+    // until drawn into.
+    pub fn with_format(
+        width: u32,
+        height: u32,
+        format: GLenum,
+        component_type: GLenum,
+        wrap: GLenum,
+        filter: GLenum,
+    ) -> RenderTexture {
+        let mut render_texture = RenderTexture {
+            framebuffer: 0,
+            texture: 0,
+            width,
+            height,
+        };
+        unsafe {
+            gl::GenTextures(1, &mut render_texture.texture);
+            gl::BindTexture(gl::TEXTURE_2D, render_texture.texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                format as GLint,
+                width as GLsizei,
+                height as GLsizei,
+                0,
+                gl::RGBA,
+                component_type,
+                ptr::null(),
+            );
+            for &(parameter, value) in &[
+                (gl::TEXTURE_MIN_FILTER, filter),
+                (gl::TEXTURE_MAG_FILTER, filter),
+                (gl::TEXTURE_WRAP_S, wrap),
+                (gl::TEXTURE_WRAP_T, wrap),
+            ] {
+                gl::TexParameteri(gl::TEXTURE_2D, parameter, value as GLint);
+            }
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            gl::GenFramebuffers(1, &mut render_texture.framebuffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, render_texture.framebuffer);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                render_texture.texture,
+                0,
+            );
+        }
+        render_texture
+    }
+
+    // Directs drawing into the texture
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+        }
+    }
+}
+
+// Dropping a render texture leaves the framebuffer binding alone, unless it
+// was bound, in which case GL reverts to the window's framebuffer
+impl Drop for RenderTexture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.framebuffer);
+            gl::DeleteTextures(1, &self.texture);
+        }
+    }
+}
@@ -23,20 +23,289 @@
  */
 
 use channel;
+use css_colors::css_color;
 use glapp;
 use sketch::*;
 
 use na::Vector4;
 
+use std::error::Error;
+use std::fmt;
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use std::sync::Mutex;
 
-#[derive(Clone)]
+// How the components of colours given as numbers and tuples are interpreted,
+// as with Processing's colorMode()
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorMode {
+    // red, green and blue
+    Rgb,
+    // hue, saturation and brightness
+    Hsb,
+    // hue, saturation and lightness
+    Hsl,
+}
+
+// The colour mode and the maximum of each of its components and alpha, kept
+// apart from the sketch so that colours can be made while it is locked
+struct ColorModeState {
+    mode: ColorMode,
+    max: Vector4<f32>,
+}
+
+lazy_static! {
+    static ref COLOR_MODE: Mutex<ColorModeState> = Mutex::new(ColorModeState {
+        mode: ColorMode::Rgb,
+        max: Vector4::new(1.0, 1.0, 1.0, 1.0),
+    });
+}
+
+// Normalized red, green, blue and alpha. Numbers and tuples are interpreted
+// with the color_mode() current when they are converted into a Color.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Color(Vector4<f32>);
 
 impl Color {
+    // Normalized red, green, blue and alpha
+    pub fn new(red: f32, green: f32, blue: f32, alpha: f32) -> Color {
+        Vector4::new(red, green, blue, alpha).into()
+    }
+
+    // Red, green and blue from 0 to 255, opaque
+    pub fn from_rgb8(red: u8, green: u8, blue: u8) -> Color {
+        Color::from_rgba8(red, green, blue, 255)
+    }
+
+    // Red, green, blue and alpha from 0 to 255
+    pub fn from_rgba8(red: u8, green: u8, blue: u8, alpha: u8) -> Color {
+        Color::new(
+            f32::from(red) / 255.0,
+            f32::from(green) / 255.0,
+            f32::from(blue) / 255.0,
+            f32::from(alpha) / 255.0,
+        )
+    }
+
+    // Parses hexadecimal RGB as in CSS, with or without a leading #, in the
+    // forms rgb, rgba, rrggbb and rrggbbaa
+    pub fn from_hex(hex: &str) -> Option<Color> {
+        let hex = hex.trim_start_matches('#');
+        if !hex.is_ascii() {
+            return None;
+        }
+        let digits = match hex.len() {
+            3 | 4 => 1,
+            6 | 8 => 2,
+            _ => return None,
+        };
+        let mut components = [255u8; 4];
+        for (i, component) in components.iter_mut().take(hex.len() / digits).enumerate() {
+            let value = u8::from_str_radix(&hex[i * digits..(i + 1) * digits], 16).ok()?;
+            // a single digit is repeated, so f is ff
+            *component = if digits == 1 { value * 17 } else { value };
+        }
+        Some(Color::from_rgba8(
+            components[0],
+            components[1],
+            components[2],
+            components[3],
+        ))
+    }
+
+    // Looks up one of the named colours of CSS, ignoring case
+    pub fn from_name(name: &str) -> Option<Color> {
+        css_color(name).map(|rgb| Color::from_rgb8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+    }
+
+    fn rgba(&self) -> Vector4<f32> {
+        self.0
+    }
+
+    // The red, green and blue are in the units of the first, second and third
+    // components of the current colour mode and the alpha in its units, as in
+    // Processing, even when the mode is not Rgb
+    pub fn red(&self) -> f32 {
+        self.rgba().x * color_mode_max().x
+    }
+
+    pub fn green(&self) -> f32 {
+        self.rgba().y * color_mode_max().y
+    }
+
+    pub fn blue(&self) -> f32 {
+        self.rgba().z * color_mode_max().z
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.rgba().w * color_mode_max().w
+    }
+
+    // The hue in the units of the first component of the current colour mode,
+    // from 0 to the maximum, where a third of the way round is green and two
+    // thirds blue. Grays have a hue of 0.
+    pub fn hue(&self) -> f32 {
+        rgb_to_hsb(&self.rgba()).0 * color_mode_max().x
+    }
+
+    // The saturation as in HSB in the units of the second component of the
+    // current colour mode
+    pub fn saturation(&self) -> f32 {
+        rgb_to_hsb(&self.rgba()).1 * color_mode_max().y
+    }
+
+    // The brightness as in HSB, the largest of red, green and blue, in the
+    // units of the third component of the current colour mode
+    pub fn brightness(&self) -> f32 {
+        rgb_to_hsb(&self.rgba()).2 * color_mode_max().z
+    }
+
+    // The lightness as in HSL, midway between the largest and smallest of red,
+    // green and blue, in the units of the third component of the current
+    // colour mode
+    pub fn lightness(&self) -> f32 {
+        let c = self.rgba();
+        0.5 * (c.x.max(c.y).max(c.z) + c.x.min(c.y).min(c.z)) * color_mode_max().z
+    }
+
+    // Interpolates between the colours in RGB with amount from 0 to 1
+    pub fn lerp(&self, to: &Color, amount: f32) -> Color {
+        let from = self.rgba();
+        (from + (to.rgba() - from) * amount).into()
+    }
+
+    // Interpolates between the colours in HSB with amount from 0 to 1, taking
+    // the shorter way around the hue circle
+    pub fn lerp_hsb(&self, to: &Color, amount: f32) -> Color {
+        let (from, to) = (self.rgba(), to.rgba());
+        let (h1, s1, b1) = rgb_to_hsb(&from);
+        let (mut h2, s2, b2) = rgb_to_hsb(&to);
+        if h2 - h1 > 0.5 {
+            h2 -= 1.0;
+        } else if h1 - h2 > 0.5 {
+            h2 += 1.0;
+        }
+        let lerp = |a: f32, b: f32| a + (b - a) * amount;
+        let (r, g, b) = hsb_to_rgb((lerp(h1, h2) + 1.0) % 1.0, lerp(s1, s2), lerp(b1, b2));
+        Color::new(r, g, b, lerp(from.w, to.w))
+    }
+
     pub fn as_slice(&self) -> &[f32] {
         self.0.as_slice()
     }
+
+    // Interprets the components of a colour mode and the alpha in its units,
+    // or opaque if there is no alpha. Values outside 0 to the maximum are
+    // clamped.
+    fn from_mode(x: f32, y: f32, z: f32, alpha: Option<f32>) -> Color {
+        let state = COLOR_MODE.lock().unwrap();
+        let max = &state.max;
+        let (x, y, z) = (scaled(x, max.x), scaled(y, max.y), scaled(z, max.z));
+        let (r, g, b) = match state.mode {
+            ColorMode::Rgb => (x, y, z),
+            ColorMode::Hsb => hsb_to_rgb(x, y, z),
+            ColorMode::Hsl => hsl_to_rgb(x, y, z),
+        };
+        let alpha = alpha.map_or(1.0, |alpha| scaled(alpha, max.w));
+        Color::new(r, g, b, alpha)
+    }
+
+    // Interprets a gray level in the units of the first component of the
+    // colour mode and the alpha in its units, or opaque if there is no alpha
+    fn from_gray(gray: f32, alpha: Option<f32>) -> Color {
+        let max = COLOR_MODE.lock().unwrap().max;
+        let gray = scaled(gray, max.x);
+        let alpha = alpha.map_or(1.0, |alpha| scaled(alpha, max.w));
+        Color::new(gray, gray, gray, alpha)
+    }
+}
+
+// Returns the hue, saturation and brightness of normalized RGB
+fn rgb_to_hsb(c: &Vector4<f32>) -> (f32, f32, f32) {
+    let max = c.x.max(c.y).max(c.z);
+    let chroma = max - c.x.min(c.y).min(c.z);
+    if chroma <= 0.0 {
+        return (0.0, 0.0, max);
+    }
+    let hue = if max == c.x {
+        ((c.y - c.z) / chroma + 6.0) % 6.0
+    } else if max == c.y {
+        (c.z - c.x) / chroma + 2.0
+    } else {
+        (c.x - c.y) / chroma + 4.0
+    };
+    (hue / 6.0, chroma / max, max)
+}
+
+fn scaled(value: f32, max: f32) -> f32 {
+    if max > 0.0 {
+        (value / max).max(0.0).min(1.0)
+    } else {
+        0.0
+    }
+}
+
+// Returns the red, green and blue of a hue from 0 to 1 with the chroma, the
+// difference between the largest and smallest components, added to m
+fn hue_to_rgb(hue: f32, chroma: f32, m: f32) -> (f32, f32, f32) {
+    let h = (hue * 6.0) % 6.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    (r + m, g + m, b + m)
+}
+
+fn hsb_to_rgb(hue: f32, saturation: f32, brightness: f32) -> (f32, f32, f32) {
+    let chroma = brightness * saturation;
+    hue_to_rgb(hue, chroma, brightness - chroma)
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (f32, f32, f32) {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    hue_to_rgb(hue, chroma, lightness - 0.5 * chroma)
+}
+
+// Formats the colour as CSS hexadecimal, #rrggbb when opaque and #rrggbbaa
+// otherwise, which from_hex() and parse() read back
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = self.rgba();
+        let byte = |value: f32| (value.max(0.0).min(1.0) * 255.0).round() as u8;
+        write!(f, "#{:02x}{:02x}{:02x}", byte(c.x), byte(c.y), byte(c.z))?;
+        if byte(c.w) < 255 {
+            write!(f, "{:02x}", byte(c.w))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseColorError(String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid colour: {}", self.0)
+    }
+}
+
+impl Error for ParseColorError {}
+
+// Parses a CSS colour name or hexadecimal RGB, so "orange".parse::<Color>()
+// and "#ffa500".parse::<Color>() are the same
+impl FromStr for Color {
+    type Err = ParseColorError;
+    fn from_str(s: &str) -> Result<Color, ParseColorError> {
+        let s = s.trim();
+        Color::from_name(s)
+            .or_else(|| Color::from_hex(s))
+            .ok_or_else(|| ParseColorError(s.to_string()))
+    }
 }
 
 impl Deref for Color {
@@ -51,19 +320,64 @@ impl DerefMut for Color {
     }
 }
 
+impl From<Vector4<f32>> for Color {
+    fn from(c: Vector4<f32>) -> Color {
+        Color(c)
+    }
+}
 impl From<(f32, f32, f32, f32)> for Color {
     fn from(c: (f32, f32, f32, f32)) -> Color {
-        Color(Vector4::new(c.0, c.1, c.2, c.3))
+        Color::from_mode(c.0, c.1, c.2, Some(c.3))
     }
 }
 impl From<(f32, f32, f32)> for Color {
     fn from(c: (f32, f32, f32)) -> Color {
-        Color(Vector4::new(c.0, c.1, c.2, 1.0))
+        Color::from_mode(c.0, c.1, c.2, None)
     }
 }
+// A gray level and alpha
+impl From<(f32, f32)> for Color {
+    fn from(c: (f32, f32)) -> Color {
+        Color::from_gray(c.0, Some(c.1))
+    }
+}
+// A gray level
 impl From<f32> for Color {
     fn from(c: f32) -> Color {
-        Color(Vector4::new(c, c, c, 1.0))
+        Color::from_gray(c, None)
+    }
+}
+
+fn color_mode_max() -> Vector4<f32> {
+    COLOR_MODE.lock().unwrap().max
+}
+
+// Sets how numbers and tuples are interpreted as colours by fill(), stroke(),
+// background() and the other functions taking colours: the components of the
+// mode and then alpha each range from 0 to their maximum. Gray levels range
+// up to the first maximum. The default is Rgb with all maxima 1. Colours
+// already made keep their RGBA, though their accessors such as red() and hue()
+// return it in the units of the current mode.
+pub fn color_mode(mode: ColorMode, max1: f32, max2: f32, max3: f32, max_alpha: f32) {
+    let mut state = COLOR_MODE.lock().unwrap();
+    state.mode = mode;
+    state.max = Vector4::new(max1, max2, max3, max_alpha);
+}
+
+// Returns the colour as normalized RGBA, interpreted with the current colour
+// mode, which is how it will be used even if the mode changes afterwards
+pub fn color<C: Into<Color>>(color: C) -> Color {
+    color.into()
+}
+
+// Interpolates between the colours with amount from 0 to 1, in HSB when the
+// colour mode is Hsb or Hsl and in RGB otherwise
+pub fn lerp_color<C1: Into<Color>, C2: Into<Color>>(from: C1, to: C2, amount: f32) -> Color {
+    let (from, to) = (from.into(), to.into());
+    let mode = COLOR_MODE.lock().unwrap().mode;
+    match mode {
+        ColorMode::Rgb => from.lerp(&to, amount),
+        ColorMode::Hsb | ColorMode::Hsl => from.lerp_hsb(&to, amount),
     }
 }
 
@@ -74,13 +388,23 @@ pub fn draw_background() {
     }));
 }
 
+// Clears the canvas to the colour. The canvas is otherwise kept from frame to
+// frame, so sketches that animate usually call this at the start of draw().
 pub fn background<C: Into<Color>>(color: C) {
-    SKETCH.lock().unwrap().background = color.into();
+    {
+        SKETCH.lock().unwrap().background = color.into();
+    }
+    // anything drawn before would be covered anyway
+    glapp::discard();
     draw_background();
 }
 
 pub fn fill<C: Into<Color>>(color: C) {
-    SKETCH.lock().unwrap().fill = color.into();
+    let mut sketch = SKETCH.lock().unwrap();
+    sketch.fill = color.into();
+    sketch.fill_gradient = None;
+    sketch.fill_texture = None;
+    sketch.normal_material = false;
 }
 
 pub fn get_fill() -> Color {
@@ -88,7 +412,10 @@ pub fn get_fill() -> Color {
 }
 
 pub fn no_fill() {
-    SKETCH.lock().unwrap().fill = (0.0, 0.0, 0.0, 0.0).into();
+    let mut sketch = SKETCH.lock().unwrap();
+    sketch.fill = Vector4::zeros().into();
+    sketch.fill_gradient = None;
+    sketch.fill_texture = None;
 }
 
 pub fn stroke<C: Into<Color>>(color: C) {
@@ -100,5 +427,5 @@ pub fn get_stroke() -> Color {
 }
 
 pub fn no_stroke() {
-    SKETCH.lock().unwrap().stroke = (0.0, 0.0, 0.0, 0.0).into();
+    SKETCH.lock().unwrap().stroke = Vector4::zeros().into();
 }
@@ -0,0 +1,190 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use custom_shape::*;
+use sketch::SKETCH;
+
+use na::{Point3, Vector3};
+
+fn weighted_sum(points: [&Point3<f32>; 4], weights: [f32; 4]) -> Vector3<f32> {
+    points[0].coords * weights[0] + points[1].coords * weights[1]
+        + points[2].coords * weights[2] + points[3].coords * weights[3]
+}
+
+fn bezier_weights(t: f32) -> [f32; 4] {
+    let u = 1.0 - t;
+    [u * u * u, 3.0 * t * u * u, 3.0 * t * t * u, t * t * t]
+}
+
+fn bezier_tangent_weights(t: f32) -> [f32; 4] {
+    let u = 1.0 - t;
+    [
+        -3.0 * u * u,
+        3.0 * u * u - 6.0 * t * u,
+        6.0 * t * u - 3.0 * t * t,
+        3.0 * t * t,
+    ]
+}
+
+// Processing's Catmull-Rom basis matrix where a tightness of 0 gives the
+// standard Catmull-Rom spline and 1 connects the points with straight lines
+fn curve_basis(s: f32) -> [[f32; 4]; 4] {
+    [
+        [(s - 1.0) * 0.5, (s + 3.0) * 0.5, (-3.0 - s) * 0.5, (1.0 - s) * 0.5],
+        [1.0 - s, (-5.0 - s) * 0.5, s + 2.0, (s - 1.0) * 0.5],
+        [(s - 1.0) * 0.5, 0.0, (1.0 - s) * 0.5, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+    ]
+}
+
+fn curve_weights(t: f32, tightness: f32) -> [f32; 4] {
+    let m = curve_basis(tightness);
+    let mut weights = [0.0; 4];
+    for i in 0..4 {
+        weights[i] = t * t * t * m[0][i] + t * t * m[1][i] + t * m[2][i] + m[3][i];
+    }
+    weights
+}
+
+fn curve_tangent_weights(t: f32, tightness: f32) -> [f32; 4] {
+    let m = curve_basis(tightness);
+    let mut weights = [0.0; 4];
+    for i in 0..4 {
+        weights[i] = 3.0 * t * t * m[0][i] + 2.0 * t * m[1][i] + m[2][i];
+    }
+    weights
+}
+
+pub fn bezier_point(
+    p1: &Point3<f32>,
+    c1: &Point3<f32>,
+    c2: &Point3<f32>,
+    p2: &Point3<f32>,
+    t: f32,
+) -> Point3<f32> {
+    Point3::from_coordinates(weighted_sum([p1, c1, c2, p2], bezier_weights(t)))
+}
+
+pub fn bezier_tangent(
+    p1: &Point3<f32>,
+    c1: &Point3<f32>,
+    c2: &Point3<f32>,
+    p2: &Point3<f32>,
+    t: f32,
+) -> Vector3<f32> {
+    weighted_sum([p1, c1, c2, p2], bezier_tangent_weights(t))
+}
+
+pub fn curve_point(
+    p1: &Point3<f32>,
+    p2: &Point3<f32>,
+    p3: &Point3<f32>,
+    p4: &Point3<f32>,
+    t: f32,
+) -> Point3<f32> {
+    let tightness = get_curve_tightness();
+    Point3::from_coordinates(weighted_sum([p1, p2, p3, p4], curve_weights(t, tightness)))
+}
+
+pub fn curve_tangent(
+    p1: &Point3<f32>,
+    p2: &Point3<f32>,
+    p3: &Point3<f32>,
+    p4: &Point3<f32>,
+    t: f32,
+) -> Vector3<f32> {
+    let tightness = get_curve_tightness();
+    weighted_sum([p1, p2, p3, p4], curve_tangent_weights(t, tightness))
+}
+
+// Flattens a cubic Bézier into detail points, excluding the start point p1
+pub fn flatten_bezier(
+    p1: &Point3<f32>,
+    c1: &Point3<f32>,
+    c2: &Point3<f32>,
+    p2: &Point3<f32>,
+    detail: u32,
+) -> Vec<Point3<f32>> {
+    (1..detail + 1)
+        .map(|i| bezier_point(p1, c1, c2, p2, i as f32 / detail as f32))
+        .collect()
+}
+
+// Flattens the Catmull-Rom segment between p2 and p3 into detail points,
+// excluding the start point p2
+pub fn flatten_curve(
+    p1: &Point3<f32>,
+    p2: &Point3<f32>,
+    p3: &Point3<f32>,
+    p4: &Point3<f32>,
+    detail: u32,
+    tightness: f32,
+) -> Vec<Point3<f32>> {
+    (1..detail + 1)
+        .map(|i| {
+            let weights = curve_weights(i as f32 / detail as f32, tightness);
+            Point3::from_coordinates(weighted_sum([p1, p2, p3, p4], weights))
+        })
+        .collect()
+}
+
+pub fn bezier(p1: Point3<f32>, c1: Point3<f32>, c2: Point3<f32>, p2: Point3<f32>) {
+    begin_shape(ShapeKind::Polygon);
+    vertex(p1);
+    bezier_vertex(c1, c2, p2);
+    end_shape(ShapeEnd::Open);
+}
+
+pub fn curve(p1: Point3<f32>, p2: Point3<f32>, p3: Point3<f32>, p4: Point3<f32>) {
+    begin_shape(ShapeKind::Polygon);
+    curve_vertex(p1);
+    curve_vertex(p2);
+    curve_vertex(p3);
+    curve_vertex(p4);
+    end_shape(ShapeEnd::Open);
+}
+
+pub fn bezier_detail(detail: u32) {
+    SKETCH.lock().unwrap().bezier_detail = detail.max(1);
+}
+
+pub fn get_bezier_detail() -> u32 {
+    SKETCH.lock().unwrap().bezier_detail
+}
+
+pub fn curve_detail(detail: u32) {
+    SKETCH.lock().unwrap().curve_detail = detail.max(1);
+}
+
+pub fn get_curve_detail() -> u32 {
+    SKETCH.lock().unwrap().curve_detail
+}
+
+pub fn curve_tightness(tightness: f32) {
+    SKETCH.lock().unwrap().curve_tightness = tightness;
+}
+
+pub fn get_curve_tightness() -> f32 {
+    SKETCH.lock().unwrap().curve_tightness
+}
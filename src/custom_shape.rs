@@ -0,0 +1,339 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use color::*;
+use curve::*;
+use line::line;
+use mesh::Mesh;
+use point::point;
+use shape::Shape;
+use sketch;
+use sketch::SKETCH;
+use stroke::polyline;
+use tessellation::*;
+use transformation::{get_normal_transformation, get_transformation};
+use utils::*;
+
+use na::Point3;
+
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShapeKind {
+    Polygon,
+    Points,
+    Lines,
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+    Quads,
+    QuadStrip,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShapeEnd {
+    Open,
+    Close,
+}
+
+struct ShapeVertex {
+    point: Point3<f32>,
+    fill: Color,
+}
+
+struct CustomShape {
+    kind: ShapeKind,
+    vertices: Vec<ShapeVertex>,
+    // exclusive end index into vertices of each completed contour
+    contour_ends: Vec<usize>,
+    // control points accumulated by curve_vertex()
+    curve_vertices: Vec<Point3<f32>>,
+}
+
+impl CustomShape {
+    fn last_vertex(&self) -> Option<Point3<f32>> {
+        let start = *self.contour_ends.last().unwrap_or(&0);
+        if self.vertices.len() > start {
+            self.vertices.last().map(|v| v.point)
+        } else {
+            None
+        }
+    }
+
+    fn push_vertices(&mut self, points: &[Point3<f32>], fill: &Color) {
+        for p in points {
+            self.vertices.push(ShapeVertex {
+                point: *p,
+                fill: fill.clone(),
+            });
+        }
+    }
+
+    fn close_contour(&mut self) {
+        self.curve_vertices.clear();
+        let start = *self.contour_ends.last().unwrap_or(&0);
+        if self.vertices.len() > start {
+            self.contour_ends.push(self.vertices.len());
+        }
+    }
+}
+
+lazy_static! {
+    static ref CUSTOM_SHAPE: Mutex<Option<CustomShape>> = Mutex::new(None);
+}
+
+pub fn begin_shape(kind: ShapeKind) {
+    *CUSTOM_SHAPE.lock().unwrap() = Some(CustomShape {
+        kind,
+        vertices: Vec::new(),
+        contour_ends: Vec::new(),
+        curve_vertices: Vec::new(),
+    });
+}
+
+pub fn begin_contour() {
+    if let Some(ref mut shape) = *CUSTOM_SHAPE.lock().unwrap() {
+        shape.close_contour();
+    }
+}
+
+pub fn end_contour() {
+    if let Some(ref mut shape) = *CUSTOM_SHAPE.lock().unwrap() {
+        shape.close_contour();
+    }
+}
+
+pub fn winding_rule(rule: WindingRule) {
+    SKETCH.lock().unwrap().winding_rule = rule;
+}
+
+pub fn vertex(p: Point3<f32>) {
+    // the fill is captured per vertex so it can be changed between vertices
+    let fill = get_fill();
+    if let Some(ref mut shape) = *CUSTOM_SHAPE.lock().unwrap() {
+        shape.curve_vertices.clear();
+        shape.vertices.push(ShapeVertex { point: p, fill });
+    }
+}
+
+// Adds a cubic Bézier from the previous vertex to p. Needs a preceding vertex()
+// in the same contour.
+pub fn bezier_vertex(c1: Point3<f32>, c2: Point3<f32>, p: Point3<f32>) {
+    let fill = get_fill();
+    let detail = get_bezier_detail();
+    if let Some(ref mut shape) = *CUSTOM_SHAPE.lock().unwrap() {
+        shape.curve_vertices.clear();
+        if let Some(start) = shape.last_vertex() {
+            shape.push_vertices(&flatten_bezier(&start, &c1, &c2, &p, detail), &fill);
+        }
+    }
+}
+
+// Adds a quadratic Bézier from the previous vertex to p by elevating it to a
+// cubic. Needs a preceding vertex() in the same contour.
+pub fn quadratic_vertex(c: Point3<f32>, p: Point3<f32>) {
+    let fill = get_fill();
+    let detail = get_bezier_detail();
+    if let Some(ref mut shape) = *CUSTOM_SHAPE.lock().unwrap() {
+        shape.curve_vertices.clear();
+        if let Some(start) = shape.last_vertex() {
+            let c1 = start + (c - start) * (2.0 / 3.0);
+            let c2 = p + (c - p) * (2.0 / 3.0);
+            shape.push_vertices(&flatten_bezier(&start, &c1, &c2, &p, detail), &fill);
+        }
+    }
+}
+
+// Adds a Catmull-Rom control point. As in Processing, the first and last
+// control points only guide the curve so drawing starts with the fourth.
+pub fn curve_vertex(p: Point3<f32>) {
+    let fill = get_fill();
+    let detail = get_curve_detail();
+    let tightness = get_curve_tightness();
+    if let Some(ref mut shape) = *CUSTOM_SHAPE.lock().unwrap() {
+        shape.curve_vertices.push(p);
+        let n = shape.curve_vertices.len();
+        if n < 4 {
+            return;
+        }
+        let points = flatten_curve(
+            &shape.curve_vertices[n - 4],
+            &shape.curve_vertices[n - 3],
+            &shape.curve_vertices[n - 2],
+            &shape.curve_vertices[n - 1],
+            detail,
+            tightness,
+        );
+        if n == 4 {
+            let start = shape.curve_vertices[1];
+            shape.push_vertices(&[start], &fill);
+        }
+        shape.push_vertices(&points, &fill);
+    }
+}
+
+pub fn end_shape(end: ShapeEnd) {
+    let mut shape;
+    match CUSTOM_SHAPE.lock().unwrap().take() {
+        Some(s) => shape = s,
+        None => return,
+    }
+    shape.close_contour();
+
+    if shape.kind != ShapeKind::Points && shape.kind != ShapeKind::Lines {
+        fill_shape(&shape);
+    }
+    if get_stroke().w > 0.0 {
+        let points: Vec<Point3<f32>> = shape.vertices.iter().map(|v| v.point).collect();
+        stroke_shape(shape.kind, &points, &shape.contour_ends, end);
+    }
+}
+
+fn fill_shape(shape: &CustomShape) {
+    let points: Vec<Point3<f32>> = shape.vertices.iter().map(|v| v.point).collect();
+    let n = points.len() as u32;
+    if n < 3 || shape.vertices.iter().all(|v| v.fill.w <= 0.0) {
+        return;
+    }
+
+    let mut mesh;
+    let rule;
+    {
+        let sketch = sketch::get_sketch();
+        mesh = Mesh::new(
+            get_transformation(&sketch.transformation),
+            get_normal_transformation(&sketch),
+            false,
+        );
+        rule = sketch.winding_rule;
+    }
+    let (l, t, r, b) = bounding_box(&points);
+    let uv = |p: &Point3<f32>| [map_f32(p.x, l, r, 0.0, 1.0), map_f32(p.y, b, t, 0.0, 1.0)];
+
+    if shape.kind == ShapeKind::Polygon {
+        // tessellation introduces new vertices along the polygon edges so
+        // interpolate the edge end points' attributes to them
+        let projected = project_to_plane(&points, &shape.contour_ends);
+        let (edge_points, triangles) = tessellate(&projected, &shape.contour_ends, rule);
+        for edge_point in &edge_points {
+            let (from, to) = (&shape.vertices[edge_point.from], &shape.vertices[edge_point.to]);
+            let point = from.point + (to.point - from.point) * edge_point.t;
+            let fill: Color = (*from.fill + (*to.fill - *from.fill) * edge_point.t).into();
+            mesh.push_vertex(&point, &uv(&point), &fill);
+        }
+        mesh.push_triangles(&triangles);
+        mesh.draw();
+        return;
+    }
+
+    for v in &shape.vertices {
+        mesh.push_vertex(&v.point, &uv(&v.point), &v.fill);
+    }
+
+    match shape.kind {
+        ShapeKind::Triangles => for i in 0..n / 3 {
+            mesh.push_triangle(3 * i, 3 * i + 1, 3 * i + 2);
+        },
+        ShapeKind::TriangleStrip => {
+            let strip: Vec<u32> = (0..n).collect();
+            mesh.push_strip(&strip);
+        }
+        ShapeKind::QuadStrip => {
+            // consecutive pairs of vertices already form a triangle strip
+            let strip: Vec<u32> = (0..n - n % 2).collect();
+            mesh.push_strip(&strip);
+        }
+        ShapeKind::TriangleFan => for i in 1..n - 1 {
+            mesh.push_triangle(0, i, i + 1);
+        },
+        ShapeKind::Quads => for i in 0..n / 4 {
+            // quads may be concave so split them on the correct diagonal
+            let first = 4 * i as usize;
+            let quad = project_to_plane(&points[first..first + 4], &[4]);
+            let triangles: Vec<u32> = triangulate(&quad)
+                .iter()
+                .map(|index| first as u32 + index)
+                .collect();
+            mesh.push_triangles(&triangles);
+        },
+        ShapeKind::Polygon | ShapeKind::Points | ShapeKind::Lines => (),
+    }
+
+    mesh.draw();
+}
+
+fn stroke_shape(kind: ShapeKind, points: &[Point3<f32>], contour_ends: &[usize], end: ShapeEnd) {
+    let n = points.len();
+    match kind {
+        ShapeKind::Points => for p in points {
+            point(*p);
+        },
+        ShapeKind::Lines => for pair in points.chunks(2) {
+            if pair.len() == 2 {
+                line(pair[0], pair[1]);
+            }
+        },
+        ShapeKind::Triangles => for triangle in points.chunks(3) {
+            if triangle.len() == 3 {
+                polyline(triangle, true);
+            }
+        },
+        ShapeKind::TriangleStrip => {
+            polyline(points, false);
+            for i in 2..n {
+                line(points[i - 2], points[i]);
+            }
+        }
+        ShapeKind::TriangleFan => {
+            if n > 0 {
+                polyline(&points[1..], false);
+            }
+            for i in 1..n {
+                line(points[0], points[i]);
+            }
+        }
+        ShapeKind::Quads => for quad in points.chunks(4) {
+            if quad.len() == 4 {
+                polyline(quad, true);
+            }
+        },
+        ShapeKind::QuadStrip => {
+            let n = n - n % 2;
+            for i in 0..n / 2 {
+                line(points[2 * i], points[2 * i + 1]);
+            }
+            for i in 2..n {
+                line(points[i - 2], points[i]);
+            }
+        }
+        ShapeKind::Polygon => {
+            // the outer contour is only closed on request but holes always are
+            let mut start = 0;
+            for (i, &contour_end) in contour_ends.iter().enumerate() {
+                polyline(&points[start..contour_end], i > 0 || end == ShapeEnd::Close);
+                start = contour_end;
+            }
+        }
+    }
+}
@@ -22,25 +22,179 @@
  * SOFTWARE.
  */
 
+use color::{get_fill, get_stroke};
+use glapp::VBO_STRIDE_N;
+use mesh::{transform_normal, Mesh};
+use rectangle::{mode_corners, ShapeMode};
 use shape;
 use shape::*;
 use sketch;
-use transformation::get_transformations;
+use sketch::{Sketch, SKETCH};
+use stroke::{polyline, stroke_depth_offset, with_coverage};
+use transformation::{get_normal_transformation, get_screen_transformations, get_transformation};
 
 use na::{Point3, Translation, Vector3};
 
 use std::f32::consts::*;
 
-const N_SEGMENTS: u32 = 64;
+// Segments are added until chords deviate from the true ellipse by at most
+// this many pixels on screen
+const MAX_DEVIATION: f32 = 0.2;
+const MAX_SEGMENTS: u32 = 1024;
 
-pub fn ellipse(center: Point3<f32>, width: f32, height: f32) {
-    Ellipse::new(center.into(), width, height, N_SEGMENTS, false).draw();
+pub fn ellipse_mode(mode: ShapeMode) {
+    SKETCH.lock().unwrap().ellipse_mode = mode;
+}
+
+// Overrides the number of segments used for ellipses and arcs, or restores
+// choosing it from their size on screen if None
+pub fn ellipse_detail(detail: Option<u32>) {
+    SKETCH.lock().unwrap().ellipse_detail = detail;
+}
+
+// The number of segments for a whole ellipse with semi-axes a and b, chosen
+// from its largest radius on screen unless overridden with ellipse_detail()
+pub fn ellipse_segments(sketch: &Sketch, center: &Point3<f32>, a: f32, b: f32) -> u32 {
+    match sketch.ellipse_detail {
+        Some(detail) => round_segments(detail),
+        None => screen_segments(sketch, center, a, b),
+    }
+}
+
+// The fewest segments keeping the chords within MAX_DEVIATION pixels of an
+// ellipse with semi-axes a and b, whatever ellipse_detail() is
+pub fn screen_segments(sketch: &Sketch, center: &Point3<f32>, a: f32, b: f32) -> u32 {
+    let (to_screen, _) = get_screen_transformations(sketch);
+    let c = to_screen * *center;
+    let pa = to_screen * (center + Vector3::new(a, 0.0, 0.0));
+    let pb = to_screen * (center + Vector3::new(0.0, b, 0.0));
+    let radius = (pa.x - c.x).hypot(pa.y - c.y).max((pb.x - c.x).hypot(pb.y - c.y));
+    if radius <= MAX_DEVIATION {
+        return round_segments(4);
+    }
+    round_segments((PI / (1.0 - MAX_DEVIATION / radius).acos()).ceil() as u32)
+}
+
+// The Ellipse vertex layout needs a multiple of 4 segments
+fn round_segments(n_segments: u32) -> u32 {
+    ((n_segments.max(4).min(MAX_SEGMENTS) + 3) / 4) * 4
+}
+
+// Returns the centre, width and height of the ellipse described by the
+// position and size according to ellipse_mode()
+fn ellipse_bounds(position: Point3<f32>, width: f32, height: f32) -> (Point3<f32>, f32, f32) {
+    let mode = SKETCH.lock().unwrap().ellipse_mode;
+    let (top_left, bottom_right) = mode_corners(mode, position, width, height);
+    (
+        Point3::new(
+            0.5 * (top_left.x + bottom_right.x),
+            0.5 * (top_left.y + bottom_right.y),
+            position.z,
+        ),
+        bottom_right.x - top_left.x,
+        top_left.y - bottom_right.y,
+    )
+}
+
+// Draws an ellipse with the position and size interpreted by ellipse_mode(),
+// which is the centre, width and height by default
+pub fn ellipse(position: Point3<f32>, width: f32, height: f32) {
+    let (center, width, height) = ellipse_bounds(position, width, height);
+    let a = width * 0.5;
+    let b = height * 0.5;
+    let n_segments = ellipse_segments(&sketch::get_sketch(), &center, a, b);
+    if get_fill().w > 0.0 {
+        Ellipse::new(center.into(), width, height, n_segments, false).draw();
+    }
+    if get_stroke().w > 0.0 {
+        let points: Vec<Point3<f32>> = (0..n_segments)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f32 / n_segments as f32;
+                center + Vector3::new(a * angle.cos(), b * angle.sin(), 0.0)
+            })
+            .collect();
+        polyline(&points, true);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArcMode {
+    // filled as a chord but only the curved edge is stroked
+    Open,
+    Chord,
+    Pie,
+}
+
+// Draws the part of an ellipse between the start and stop angles, which are in
+// radians anticlockwise from the positive x-axis as with rotate(). The
+// position and size are interpreted by ellipse_mode().
+pub fn arc(position: Point3<f32>, width: f32, height: f32, start: f32, stop: f32, mode: ArcMode) {
+    if stop <= start {
+        return;
+    }
+    let (center, width, height) = ellipse_bounds(position, width, height);
+    let sweep = (stop - start).min(2.0 * PI);
+    let a = width * 0.5;
+    let b = height * 0.5;
+    let n_ellipse_segments = ellipse_segments(&sketch::get_sketch(), &center, a, b);
+    let n_segments = ((n_ellipse_segments as f32 * sweep / (2.0 * PI)).ceil() as u32).max(1);
+    let points: Vec<Point3<f32>> = (0..n_segments + 1)
+        .map(|i| {
+            let angle = start + sweep * i as f32 / n_segments as f32;
+            center + Vector3::new(a * angle.cos(), b * angle.sin(), 0.0)
+        })
+        .collect();
+
+    let fill;
+    let stroke;
+    let mut mesh;
+    {
+        let sketch = sketch::get_sketch();
+        fill = sketch.fill.clone();
+        stroke = sketch.stroke.clone();
+        mesh = Mesh::new(
+            get_transformation(&sketch.transformation),
+            get_normal_transformation(&sketch),
+            false,
+        );
+    }
+
+    let uv = |p: &Point3<f32>| {
+        [
+            0.5 * ((p.x - center.x) / a + 1.0),
+            0.5 * ((p.y - center.y) / b + 1.0),
+        ]
+    };
+    if fill.w > 0.0 {
+        if mode == ArcMode::Pie {
+            mesh.push_vertex(&center, &[0.5, 0.5], &fill);
+        }
+        for p in &points {
+            mesh.push_vertex(p, &uv(p), &fill);
+        }
+        // the arc is convex so a fan from its first vertex covers it
+        for i in 1..mesh.n_vertices() as u32 - 1 {
+            mesh.push_triangle(0, i, i + 1);
+        }
+        mesh.draw();
+    }
+
+    if stroke.w > 0.0 {
+        match mode {
+            ArcMode::Open => polyline(&points, false),
+            ArcMode::Chord => polyline(&points, sweep < 2.0 * PI),
+            ArcMode::Pie => {
+                let mut pie = points;
+                pie.push(center);
+                polyline(&pie, true);
+            }
+        }
+    }
 }
 
 pub struct Ellipse {
-    vertex_data: [f32; 9 * N_SEGMENTS as usize],
-    index_data: [u32; N_SEGMENTS as usize],
-    n_segments: usize,
+    vertex_data: Vec<f32>,
+    index_data: Vec<u32>,
     is_stroke: bool,
 }
 
@@ -54,9 +208,8 @@ impl Ellipse {
     ) -> Ellipse {
         let n_segments = n_segments as usize;
         let mut ellipse = Ellipse {
-            vertex_data: [0.0; 9 * N_SEGMENTS as usize],
-            index_data: [0; N_SEGMENTS as usize],
-            n_segments,
+            vertex_data: vec![0.0; VBO_STRIDE_N * n_segments],
+            index_data: (0..n_segments as u32).collect(),
             is_stroke,
         };
 
@@ -69,60 +222,63 @@ impl Ellipse {
         let sketch = sketch::get_sketch();
         let color;
         if is_stroke {
-            color = &sketch.stroke;
+            color = with_coverage(sketch.stroke_weight, &sketch.stroke).1;
         } else {
-            color = &sketch.fill;
+            color = sketch.fill.clone();
         }
 
-        let transform;
-        {
-            let transformations = get_transformations();
-            if let Some(transformation) = transformations.last() {
-                transform = sketch.transformation * transformation
-                    * Translation::from_vector(center - Point3::origin());
-            } else {
-                transform =
-                    sketch.transformation * Translation::from_vector(center - Point3::origin());
-            }
+        let mut transform = get_transformation(&sketch.transformation)
+            * Translation::from_vector(center - Point3::origin());
+        if is_stroke {
+            transform = stroke_depth_offset(&sketch) * transform;
         }
 
-        assign_vertex(
+        let normal = match get_normal_transformation(&sketch) {
+            Some(normal_transform) => transform_normal(&normal_transform, &Vector3::z()),
+            None => Vector3::z(),
+        };
+        assign_vertex_normal(
             &(transform * Point3::new(-a, 0.0, 0.0)),
+            &normal,
             &[0.0, 0.5],
-            color,
-            &mut ellipse.vertex_data[0 * 9..],
+            &color,
+            &mut ellipse.vertex_data[0 * VBO_STRIDE_N..],
         );
         let last_index = n_segments - 1;
-        assign_vertex(
+        assign_vertex_normal(
             &(transform * Point3::new(a, 0.0, 0.0)),
+            &normal,
             &[1.0, 0.5],
             &color,
-            &mut ellipse.vertex_data[last_index * 9..],
+            &mut ellipse.vertex_data[last_index * VBO_STRIDE_N..],
         );
         let center_index = last_index / 2;
         let mut n_points_remaining = n_segments - 2;
         if n_segments % 4 == 0 {
-            assign_vertex(
+            assign_vertex_normal(
                 &(transform * Point3::new(0.0, -b, 0.0)),
+                &normal,
                 &[0.5, 0.0],
                 &color,
-                &mut ellipse.vertex_data[center_index * 9..],
+                &mut ellipse.vertex_data[center_index * VBO_STRIDE_N..],
             );
-            assign_vertex(
+            assign_vertex_normal(
                 &(transform * Point3::new(0.0, b, 0.0)),
+                &normal,
                 &[0.5, 1.0],
-                color,
-                &mut ellipse.vertex_data[(center_index + 1) * 9..],
+                &color,
+                &mut ellipse.vertex_data[(center_index + 1) * VBO_STRIDE_N..],
             );
             n_points_remaining -= 2;
         }
 
         let do_vertex = |p: Point3<f32>, offsets: &Vector3<f32>, vd: &mut [f32]| {
             let point = p + offsets;
-            assign_vertex(
+            assign_vertex_normal(
                 &(transform * point),
+                &normal,
                 &[0.5 * (point.x / a + 1.0), 0.5 * (point.y / b + 1.0)],
-                color,
+                &color,
                 vd,
             );
         };
@@ -136,34 +292,38 @@ impl Ellipse {
                 Vector3::new(-2.0 * p.x, -2.0 * p.y, 0.0), // br
                 Vector3::new(-2.0 * p.x, 0.0, 0.0),        // tr
             ];
-            do_vertex(p, &offsets[0], &mut ellipse.vertex_data[(2 * i - 1) * 9..]);
-            do_vertex(p, &offsets[1], &mut ellipse.vertex_data[(2 * i) * 9..]);
+            do_vertex(
+                p,
+                &offsets[0],
+                &mut ellipse.vertex_data[(2 * i - 1) * VBO_STRIDE_N..],
+            );
+            do_vertex(
+                p,
+                &offsets[1],
+                &mut ellipse.vertex_data[(2 * i) * VBO_STRIDE_N..],
+            );
             do_vertex(
                 p,
                 &offsets[2],
-                &mut ellipse.vertex_data[(last_index - (2 * i)) * 9..],
+                &mut ellipse.vertex_data[(last_index - (2 * i)) * VBO_STRIDE_N..],
             );
             do_vertex(
                 p,
                 &offsets[3],
-                &mut ellipse.vertex_data[(last_index - (2 * i - 1)) * 9..],
+                &mut ellipse.vertex_data[(last_index - (2 * i - 1)) * VBO_STRIDE_N..],
             );
         }
 
-        for i in 0..n_segments {
-            ellipse.index_data[i] = i as u32;
-        }
-
         ellipse
     }
 }
 
 impl Shape for Ellipse {
     fn vertex_data(&self) -> &[f32] {
-        &self.vertex_data[..self.n_segments * 9]
+        &self.vertex_data
     }
     fn index_data(&self) -> &[u32] {
-        &self.index_data[..self.n_segments]
+        &self.index_data
     }
     fn vertex_shader(&self) -> Option<String> {
         None
@@ -177,4 +337,11 @@ impl Shape for Ellipse {
     fn is_stroke(&self) -> bool {
         self.is_stroke
     }
+    fn uv_edges(&self) -> UvEdges {
+        if self.is_stroke {
+            UvEdges::None
+        } else {
+            UvEdges::Ellipse
+        }
+    }
 }
@@ -0,0 +1,95 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use filter::FilterShader;
+use glapp;
+use sketch::SKETCH;
+
+use na::Vector4;
+
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref NEXT_FEEDBACK_ID: Mutex<u32> = Mutex::new(0);
+}
+
+// A pair of float textures holding the state of a simulation on the GPU, such
+// as reaction-diffusion or the game of life. Each step runs a shader that reads
+// the state from one texture and writes the next state into the other, and
+// then they swap. The state starts out as zeros and wraps around at the edges,
+// and is sampled without interpolation so each texel draws as a sharp cell.
+pub struct FeedbackBuffer {
+    pub id: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FeedbackBuffer {
+    pub fn new(width: u32, height: u32) -> FeedbackBuffer {
+        let id = {
+            let mut next_id = NEXT_FEEDBACK_ID.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        glapp::create_feedback_buffer(id, width, height);
+        FeedbackBuffer { id, width, height }
+    }
+
+    // Runs the shader over every texel of the state. As well as the uniforms
+    // it sets, the shader is given the current state as
+    // `uniform sampler2D state`, the size of a texel as
+    // `uniform vec2 texel_size`, the number of steps so far as
+    // `uniform int step_count` and the texture coordinates as
+    // `in vec2 tex_coord`, and writes the next state to `out vec4 frag_color`.
+    pub fn step(&self, shader: &FilterShader) {
+        // shapes drawn before the step see the state from before it
+        glapp::render();
+        glapp::step_feedback_buffer(self.id, shader.clone());
+    }
+
+    // Draws the state over the whole canvas, along with everything drawn
+    // before it
+    pub fn display(&self) {
+        glapp::render();
+        glapp::display_feedback_buffer(self.id);
+    }
+}
+
+impl Drop for FeedbackBuffer {
+    fn drop(&mut self) {
+        glapp::delete_feedback_buffer(self.id);
+    }
+}
+
+// Fills shapes drawn afterwards with the state of the feedback buffer, which
+// is sampled at their texture coordinates, until fill(), no_fill() or
+// fill_gradient()
+pub fn fill_feedback(buffer: &FeedbackBuffer) {
+    let mut sketch = SKETCH.lock().unwrap();
+    // the shapes' vertex colours multiply the state
+    sketch.fill = Vector4::new(1.0, 1.0, 1.0, 1.0).into();
+    sketch.fill_gradient = None;
+    sketch.fill_texture = Some(buffer.id);
+    sketch.normal_material = false;
+}
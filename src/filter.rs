@@ -0,0 +1,99 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use glapp;
+
+use std::fs::File;
+use std::io;
+use std::io::Read;
+
+// Post-processing of everything drawn so far in the frame, as with
+// Processing's filter()
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    // a Gaussian blur with the radius in pixels
+    Blur(f32),
+    // white where the luminance is at least the level from 0 to 1 and black
+    // elsewhere
+    Threshold(f32),
+    // the luminance as a gray level
+    Gray,
+    Invert,
+    // limits each of red, green and blue to the number of levels
+    Posterize(u32),
+    // shrinks light areas by taking the darkest neighbouring pixel
+    Erode,
+    // grows light areas by taking the lightest neighbouring pixel
+    Dilate,
+    Shader(FilterShader),
+}
+
+impl<'a> From<&'a FilterShader> for Filter {
+    fn from(shader: &'a FilterShader) -> Filter {
+        Filter::Shader(shader.clone())
+    }
+}
+
+// A custom post-processing fragment shader for filter(). It is given the
+// canvas as `uniform sampler2D canvas`, the size of a pixel in texture
+// coordinates as `uniform vec2 texel_size` and the texture coordinates as
+// `in vec2 tex_coord`, and writes `out vec4 frag_color`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterShader {
+    pub fragment_shader: String,
+    // float, vec2, vec3 and vec4 uniforms by name
+    pub uniforms: Vec<(String, Vec<f32>)>,
+}
+
+impl FilterShader {
+    pub fn new(fragment_shader: &str) -> FilterShader {
+        FilterShader {
+            fragment_shader: fragment_shader.to_string(),
+            uniforms: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> io::Result<FilterShader> {
+        let mut fragment_shader = String::new();
+        File::open(path)?.read_to_string(&mut fragment_shader)?;
+        Ok(FilterShader::new(&fragment_shader))
+    }
+
+    // Sets a uniform of one to four floats for the next time the shader is
+    // used
+    pub fn set_uniform(&mut self, name: &str, value: &[f32]) {
+        let value = value[..value.len().min(4)].to_vec();
+        match self.uniforms.iter().position(|uniform| uniform.0 == name) {
+            Some(i) => self.uniforms[i].1 = value,
+            None => self.uniforms.push((name.to_string(), value)),
+        }
+    }
+}
+
+// Draws everything so far and then filters the canvas, so shapes drawn
+// afterwards are not filtered
+pub fn filter<F: Into<Filter>>(filter: F) {
+    glapp::render();
+    glapp::apply_filter(filter.into());
+}
@@ -25,29 +25,77 @@
 extern crate glutin;
 extern crate libc;
 
+use blend::BlendMode;
+use camera::{default_projection, default_view};
+use canvas::{Canvas, RenderTexture};
 use channel;
 use color::*;
-use sketch::SKETCH;
+use filter::{Filter, FilterShader};
+use gradient::{Gradient, GradientKind, MAX_GRADIENT_STOPS};
+use light::{Light, LightKind, MAX_LIGHTS};
+use material::Material;
+use sketch::{Renderer, SKETCH};
 use shader::*;
+use shape::UvEdges;
 
 use self::glutin::GlContext;
 use gl;
 use gl::types::*;
-use na::{Matrix4, Transform3, Vector3};
+use na::Matrix4;
 
 use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::ffi::CString;
 use std::mem::size_of;
 use std::os::raw::c_void;
 use std::process::exit;
 use std::ptr;
-use std::sync::{mpsc, Mutex};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub struct GLShape {
     pub shader_program: GLuint,
-    pub index_byte_offset: GLuint,
-    pub n_triangles: u32,
+    pub first_index: u32,
+    pub n_indices: u32,
+    pub blend_mode: BlendMode,
+    pub depth_test: bool,
+    pub depth_mask: bool,
+    // the mean view space z of translucent shapes drawn after all the others
+    // with hint(EnableDepthSort)
+    pub sort_depth: Option<f32>,
+    // the lights the shape was drawn with, if any, and its material for them
+    pub lights: Option<Arc<Vec<Light>>>,
+    pub material: Option<Material>,
+    // the gradient the shape was filled with, if any
+    pub gradient: Option<Gradient>,
+    // the id of the feedback buffer the shape was filled with, if any
+    pub texture: Option<u32>,
+    // the edges to smooth in the fragment shader, if multisampling is
+    // unavailable
+    pub uv_edges: UvEdges,
+}
+
+// A range of the index buffer drawn with one call
+struct GLBatch {
+    first_index: u32,
+    n_indices: u32,
+    blend_mode: BlendMode,
+    depth_test: bool,
+    depth_mask: bool,
+    lights: Option<Arc<Vec<Light>>>,
+    material: Option<Material>,
+    gradient: Option<Gradient>,
+    texture: Option<u32>,
+    uv_edges: UvEdges,
+}
+
+// The textures of a FeedbackBuffer, with the current state in textures[current]
+struct FeedbackTextures {
+    textures: Vec<RenderTexture>,
+    current: usize,
+    n_steps: i32,
 }
 
 pub const DEFAULT_WIDTH: u32 = 640;
@@ -68,7 +116,10 @@ lazy_static! {
     static ref DEFAULT_SHADER_PROGRAM: Mutex<GLuint> = Mutex::new(0);
     static ref SHADERS: Mutex<HashMap<String, GLuint>> = Mutex::new(HashMap::new());
     static ref GL_SHAPES: Mutex<Vec<GLShape>> = Mutex::new(Vec::new());
-    static ref INDEX_BYTES_OFFSET: Mutex<u32> = Mutex::new(0);
+    static ref NEXT_SHAPE_INDEX: Mutex<u32> = Mutex::new(0);
+    // whether smooth() fell back to smoothing edges in the fragment shader
+    // because the canvas could not be multisampled
+    static ref SMOOTH_UV_EDGES: Mutex<bool> = Mutex::new(false);
 }
 
 pub fn listen(rx: mpsc::Receiver<channel::MessageType>) {
@@ -124,6 +175,16 @@ fn get_default_shader_program_gl() -> GLuint {
     program
 }
 
+fn get_lit_shader_program_gl() -> GLuint {
+    let mut program: GLuint = 0;
+    GLAPP.with(|handle| {
+        if let Some(ref glapp) = *handle.borrow() {
+            program = glapp.lit_shader_program;
+        }
+    });
+    program
+}
+
 pub fn get_default_shader_program() -> GLuint {
     let shader_program: GLuint = *DEFAULT_SHADER_PROGRAM.lock().unwrap();
     if shader_program > 0 {
@@ -181,6 +242,13 @@ pub fn get_shader_program(
     shader_program
 }
 
+fn clear_depth() {
+    unsafe {
+        gl::DepthMask(gl::TRUE);
+        gl::Clear(gl::DEPTH_BUFFER_BIT);
+    }
+}
+
 pub fn background(color: &Color) {
     unsafe {
         gl::ClearColor(color.x, color.y, color.z, color.w);
@@ -188,17 +256,21 @@ pub fn background(color: &Color) {
     }
 }
 
+// Sets the size of the window with the P2D renderer
 pub fn size(w: u32, h: u32) {
+    size_with_renderer(w, h, Renderer::P2D);
+}
+
+// Sets the size of the window and how shapes are drawn in it, which should
+// only be done once, in setup()
+pub fn size_with_renderer(w: u32, h: u32, renderer: Renderer) {
     {
         let mut sketch = SKETCH.lock().unwrap();
+        sketch.renderer = renderer;
         sketch.width = w.clone();
         sketch.height = h.clone();
-        sketch.transformation =
-            Transform3::from_matrix_unchecked(Matrix4::new_nonuniform_scaling(&Vector3::new(
-                2.0 / w as f32,
-                2.0 / h as f32,
-                2.0 / h as f32,
-            )));
+        sketch.transformation = default_view(h);
+        sketch.projection = default_projection(w, h);
     }
     channel::push(Box::new(move || {
         GLAPP.with(|handle| {
@@ -211,12 +283,59 @@ pub fn size(w: u32, h: u32) {
     channel::send();
 }
 
+// Sets how many samples per pixel the canvas has for anti-aliasing. If the GL
+// cannot multisample, the edges of rectangles, ellipses and the sides of
+// strokes are smoothed in the fragment shader instead, but those of triangles,
+// quads, arcs, rounded rectangles, custom shapes and the ends of strokes are
+// not. The default is 2. As in Processing this belongs in setup(), though what
+// has been drawn is kept if it is called later.
+pub fn smooth(samples: u32) {
+    SKETCH.lock().unwrap().smooth = samples;
+    channel::push(Box::new(move || {
+        GLAPP.with(|handle| {
+            if let Some(ref mut glapp) = *handle.borrow_mut() {
+                glapp.create_canvas(true);
+            }
+        });
+    }));
+    channel::send();
+}
+
+// Draws aliased edges, as for pixel art
+pub fn no_smooth() {
+    smooth(0);
+}
+
 const N_BUFFERS: usize = 1;
 
 struct GLApp {
     events_loop: glutin::EventsLoop,
     gl_window: glutin::GlWindow,
     default_shader_program: GLuint,
+    lit_shader_program: GLuint,
+    canvas: Option<Canvas>,
+    filter_shader_program: GLuint,
+    // programs for custom filters and shader sketches by fragment shader
+    // source, which draw the full-viewport rectangle of FILTER_VERTEX_SHADER
+    viewport_shader_programs: HashMap<String, GLuint>,
+    // the two textures filter passes ping-pong between
+    filter_textures: Vec<RenderTexture>,
+    // an empty vertex array for the full-viewport rectangle of filter passes
+    filter_vao: GLuint,
+    // the mouse in pixels from the bottom-left of the window, where its button
+    // was last pressed and where it was when last moved with the button down
+    mouse_position: (f32, f32),
+    mouse_press_position: (f32, f32),
+    mouse_drag_position: (f32, f32),
+    mouse_pressed: bool,
+    // when the first and latest frames of a shader sketch were drawn, and how
+    // many have been
+    shader_start_time: Option<Instant>,
+    shader_frame_time: Option<Instant>,
+    shader_frame: i32,
+    // the last frame of a shader sketch, for feedback effects
+    previous_frame: Option<RenderTexture>,
+    feedback_buffers: HashMap<u32, FeedbackTextures>,
     vaos: [GLuint; N_BUFFERS],
     vbos: [GLuint; N_BUFFERS],
     ebos: [GLuint; N_BUFFERS],
@@ -238,6 +357,7 @@ impl GLApp {
         let window = glutin::WindowBuilder::new()
             .with_title("p5-rs sketch")
             .with_dimensions(w, h);
+        // shapes are drawn into a canvas with its own depth buffer
         let context = glutin::ContextBuilder::new().with_vsync(true);
         let gl_window = glutin::GlWindow::new(window, context, &events_loop).unwrap();
 
@@ -245,6 +365,21 @@ impl GLApp {
             events_loop,
             gl_window,
             default_shader_program: 0,
+            lit_shader_program: 0,
+            canvas: None,
+            filter_shader_program: 0,
+            viewport_shader_programs: HashMap::new(),
+            filter_textures: Vec::new(),
+            filter_vao: 0,
+            mouse_position: (0.0, 0.0),
+            mouse_press_position: (0.0, 0.0),
+            mouse_drag_position: (0.0, 0.0),
+            mouse_pressed: false,
+            shader_start_time: None,
+            shader_frame_time: None,
+            shader_frame: 0,
+            previous_frame: None,
+            feedback_buffers: HashMap::new(),
             vaos: [0; N_BUFFERS],
             vbos: [0; N_BUFFERS],
             ebos: [0; N_BUFFERS],
@@ -260,15 +395,59 @@ impl GLApp {
 
         gl::load_with(|symbol| self.gl_window.get_proc_address(symbol) as *const _);
         unsafe {
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            // the blend function is set for each batch of shapes by render()
             gl::Enable(gl::BLEND);
+            // shapes at the same depth are drawn over earlier ones as in 2D
+            gl::DepthFunc(gl::LEQUAL);
         }
 
         let vertex_shader = compile_shader(&DEFAULT_VERTEX_SHADER, gl::VERTEX_SHADER);
         let fragment_shader = compile_shader(&DEFAULT_FRAGMENT_SHADER, gl::FRAGMENT_SHADER);
         self.default_shader_program = link_program(vertex_shader, fragment_shader);
 
+        let vertex_shader = compile_shader(&LIT_VERTEX_SHADER, gl::VERTEX_SHADER);
+        let fragment_shader = compile_shader(&LIT_FRAGMENT_SHADER, gl::FRAGMENT_SHADER);
+        self.lit_shader_program = link_program(vertex_shader, fragment_shader);
+
+        let vertex_shader = compile_shader(&FILTER_VERTEX_SHADER, gl::VERTEX_SHADER);
+        let fragment_shader = compile_shader(&FILTER_FRAGMENT_SHADER, gl::FRAGMENT_SHADER);
+        self.filter_shader_program = link_program(vertex_shader, fragment_shader);
+        unsafe {
+            gl::GenVertexArrays(1, &mut self.filter_vao);
+        }
+
         self.init_gl_objects();
+        self.create_canvas(false);
+    }
+
+    // Replaces the canvas with one of the sketch's size and smoothness. The
+    // canvas keeps what is drawn on it from frame to frame, so it starts out
+    // cleared to the background colour, or with what was drawn on the old
+    // canvas if keep_contents and it is the same size.
+    fn create_canvas(&mut self, keep_contents: bool) {
+        let (w, h, samples, color) = {
+            let sketch = SKETCH.lock().unwrap();
+            (sketch.width, sketch.height, sketch.smooth, sketch.background.clone())
+        };
+        // the old canvas is deleted before the new one is made as that
+        // unbinds it
+        let contents = match self.canvas.take() {
+            Some(ref old) if keep_contents && old.width == w && old.height == h => {
+                let texture = RenderTexture::new(w, h);
+                old.resolve_into(&texture);
+                Some(texture)
+            }
+            _ => None,
+        };
+        let canvas = Canvas::new(w, h, samples);
+        *SMOOTH_UV_EDGES.lock().unwrap() = samples > 0 && canvas.samples == 0;
+        canvas.bind();
+        background(&color);
+        clear_depth();
+        self.canvas = Some(canvas);
+        if let Some(texture) = contents {
+            self.draw_texture(texture.texture);
+        }
     }
 
     fn init_gl_objects(&mut self) {
@@ -317,6 +496,17 @@ impl GLApp {
                     (5 * size_of::<GLfloat>()) as *const c_void,
                 );
                 gl::EnableVertexAttribArray(col_attr);
+
+                let normal_attr: GLuint = 3;
+                gl::VertexAttribPointer(
+                    normal_attr,
+                    3,
+                    gl::FLOAT,
+                    gl::FALSE as GLboolean,
+                    (VBO_STRIDE_N * size_of::<GLfloat>()) as GLint,
+                    (9 * size_of::<GLfloat>()) as *const c_void,
+                );
+                gl::EnableVertexAttribArray(normal_attr);
             }
         }
     }
@@ -362,16 +552,274 @@ impl GLApp {
         )
     }
 
+    fn viewport_shader_program(&mut self, fragment_shader: &str) -> GLuint {
+        if let Some(&program) = self.viewport_shader_programs.get(fragment_shader) {
+            return program;
+        }
+        let vertex_shader = compile_shader(&FILTER_VERTEX_SHADER, gl::VERTEX_SHADER);
+        let program = link_program(
+            vertex_shader,
+            compile_shader(fragment_shader, gl::FRAGMENT_SHADER),
+        );
+        self.viewport_shader_programs
+            .insert(fragment_shader.to_string(), program);
+        program
+    }
+
+    // Runs the filter's passes over the canvas. The canvas is copied into the
+    // first filter texture and each pass but the last draws from one filter
+    // texture into the other, with the last drawing back into the canvas.
+    fn filter(&mut self, filter: &Filter) {
+        let (w, h) = match self.canvas {
+            Some(ref canvas) => (canvas.width, canvas.height),
+            None => return,
+        };
+        if self
+            .filter_textures
+            .first()
+            .map_or(true, |texture| texture.width != w || texture.height != h)
+        {
+            self.filter_textures.clear();
+            self.filter_textures.push(RenderTexture::new(w, h));
+            self.filter_textures.push(RenderTexture::new(w, h));
+        }
+
+        let program = match *filter {
+            Filter::Shader(ref shader) => self.viewport_shader_program(&shader.fragment_shader),
+            _ => self.filter_shader_program,
+        };
+        let (kind, parameter) = match *filter {
+            Filter::Blur(radius) => (0, radius),
+            Filter::Threshold(level) => (1, level),
+            Filter::Gray => (2, 0.0),
+            Filter::Invert => (3, 0.0),
+            Filter::Posterize(levels) => (4, levels as f32),
+            Filter::Erode => (5, 0.0),
+            Filter::Dilate => (6, 0.0),
+            Filter::Shader(_) => (-1, 0.0),
+        };
+        // blurs are separable into horizontal and vertical passes
+        let directions: &[[f32; 2]] = match *filter {
+            Filter::Blur(_) => &[[1.0, 0.0], [0.0, 1.0]],
+            _ => &[[0.0, 0.0]],
+        };
+
+        let canvas = self.canvas.as_ref().unwrap();
+        canvas.resolve_into(&self.filter_textures[0]);
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::UseProgram(program);
+            gl::BindVertexArray(self.filter_vao);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::Uniform1i(uniform_location(program, "canvas"), 0);
+            gl::Uniform2f(
+                uniform_location(program, "texel_size"),
+                1.0 / w as f32,
+                1.0 / h as f32,
+            );
+            gl::Uniform1i(uniform_location(program, "filter_kind"), kind);
+            gl::Uniform1f(uniform_location(program, "filter_parameter"), parameter);
+            if let Filter::Shader(ref shader) = *filter {
+                set_shader_uniforms(program, shader);
+            }
+            for (i, direction) in directions.iter().enumerate() {
+                if i + 1 == directions.len() {
+                    canvas.bind();
+                } else {
+                    self.filter_textures[(i + 1) % 2].bind();
+                }
+                gl::BindTexture(gl::TEXTURE_2D, self.filter_textures[i % 2].texture);
+                gl::Uniform2fv(
+                    uniform_location(program, "blur_direction"),
+                    1,
+                    direction.as_ptr(),
+                );
+                gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            }
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::Enable(gl::BLEND);
+        }
+    }
+
+    // Draws a frame of a shader sketch over the whole canvas with Shadertoy's
+    // uniforms, and keeps a copy of it for the next frame
+    fn draw_shader_sketch(&mut self, fragment_shader: &str) {
+        let (w, h) = match self.canvas {
+            Some(ref canvas) => (canvas.width, canvas.height),
+            None => return,
+        };
+        if self.previous_frame
+            .as_ref()
+            .map_or(true, |frame| frame.width != w || frame.height != h)
+        {
+            // the first frame sees a cleared texture
+            let frame = RenderTexture::new(w, h);
+            frame.bind();
+            unsafe {
+                gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
+            self.previous_frame = Some(frame);
+        }
+        let program = self.viewport_shader_program(fragment_shader);
+
+        let now = Instant::now();
+        let start_time = *self.shader_start_time.get_or_insert(now);
+        let frame_time = self.shader_frame_time.unwrap_or(now);
+        self.shader_frame_time = Some(now);
+        let seconds = |duration: Duration| {
+            duration.as_secs() as f32 + duration.subsec_nanos() as f32 * 1e-9
+        };
+        // Shadertoy's iMouse has where the mouse was last dragged in xy and
+        // where the button was pressed in zw, negated once it is released
+        let (x, y) = self.mouse_drag_position;
+        let (press_x, press_y) = self.mouse_press_position;
+        let mouse = if self.mouse_pressed {
+            [x, y, press_x, press_y]
+        } else {
+            [x, y, -press_x, -press_y]
+        };
+
+        let canvas = self.canvas.as_ref().unwrap();
+        let previous_frame = self.previous_frame.as_ref().unwrap();
+        canvas.bind();
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::UseProgram(program);
+            gl::BindVertexArray(self.filter_vao);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, previous_frame.texture);
+            gl::Uniform1i(uniform_location(program, "iChannel0"), 0);
+            gl::Uniform3f(uniform_location(program, "iResolution"), w as f32, h as f32, 1.0);
+            gl::Uniform1f(uniform_location(program, "iTime"), seconds(now - start_time));
+            gl::Uniform1f(uniform_location(program, "iTimeDelta"), seconds(now - frame_time));
+            gl::Uniform1i(uniform_location(program, "iFrame"), self.shader_frame);
+            gl::Uniform4fv(uniform_location(program, "iMouse"), 1, mouse.as_ptr());
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::Enable(gl::BLEND);
+        }
+        canvas.resolve_into(previous_frame);
+        self.shader_frame += 1;
+    }
+
+    fn create_feedback_buffer(&mut self, id: u32, w: u32, h: u32) {
+        let mut textures = Vec::with_capacity(2);
+        for _ in 0..2 {
+            // nearest filtering keeps each cell of the state crisp when drawn
+            let texture =
+                RenderTexture::with_format(w, h, gl::RGBA32F, gl::FLOAT, gl::REPEAT, gl::NEAREST);
+            texture.bind();
+            unsafe {
+                gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
+            textures.push(texture);
+        }
+        self.feedback_buffers.insert(
+            id,
+            FeedbackTextures {
+                textures,
+                current: 0,
+                n_steps: 0,
+            },
+        );
+        if let Some(ref canvas) = self.canvas {
+            canvas.bind();
+        }
+    }
+
+    // Draws the next state of the feedback buffer from the current one with
+    // the shader
+    fn step_feedback_buffer(&mut self, id: u32, shader: &FilterShader) {
+        let program = self.viewport_shader_program(&shader.fragment_shader);
+        let buffer = match self.feedback_buffers.get_mut(&id) {
+            Some(buffer) => buffer,
+            None => return,
+        };
+        {
+            let source = &buffer.textures[buffer.current];
+            let target = &buffer.textures[1 - buffer.current];
+            target.bind();
+            unsafe {
+                gl::Disable(gl::BLEND);
+                gl::Disable(gl::DEPTH_TEST);
+                gl::UseProgram(program);
+                gl::BindVertexArray(self.filter_vao);
+                gl::ActiveTexture(gl::TEXTURE0);
+                gl::BindTexture(gl::TEXTURE_2D, source.texture);
+                gl::Uniform1i(uniform_location(program, "state"), 0);
+                gl::Uniform2f(
+                    uniform_location(program, "texel_size"),
+                    1.0 / source.width as f32,
+                    1.0 / source.height as f32,
+                );
+                gl::Uniform1i(uniform_location(program, "step_count"), buffer.n_steps);
+                set_shader_uniforms(program, shader);
+                gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+                gl::BindTexture(gl::TEXTURE_2D, 0);
+                gl::Enable(gl::BLEND);
+            }
+        }
+        buffer.current = 1 - buffer.current;
+        buffer.n_steps += 1;
+        if let Some(ref canvas) = self.canvas {
+            canvas.bind();
+        }
+    }
+
+    fn feedback_texture(&self, id: u32) -> Option<GLuint> {
+        self.feedback_buffers
+            .get(&id)
+            .map(|buffer| buffer.textures[buffer.current].texture)
+    }
+
+    // Copies the current state of the feedback buffer over the canvas
+    fn display_feedback_buffer(&mut self, id: u32) {
+        let texture = match self.feedback_texture(id) {
+            Some(texture) => texture,
+            None => return,
+        };
+        self.draw_texture(texture);
+    }
+
+    // Copies the texture over the whole canvas
+    fn draw_texture(&mut self, texture: GLuint) {
+        let program = self.viewport_shader_program(DISPLAY_FRAGMENT_SHADER);
+        if let Some(ref canvas) = self.canvas {
+            canvas.bind();
+        }
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::UseProgram(program);
+            gl::BindVertexArray(self.filter_vao);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::Uniform1i(uniform_location(program, "canvas"), 0);
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::Enable(gl::BLEND);
+        }
+    }
+
     pub fn size(&mut self, w: u32, h: u32) {
         if w == 0 || h == 0 {
             return;
         }
         self.gl_window.window().set_inner_size(w, h);
         self.gl_window.resize(w, h);
+        self.create_canvas(false);
     }
 
     pub fn poll_events(&mut self) {
         let gl_window = &self.gl_window;
+        let mouse_position = &mut self.mouse_position;
+        let mouse_press_position = &mut self.mouse_press_position;
+        let mouse_drag_position = &mut self.mouse_drag_position;
+        let mouse_pressed = &mut self.mouse_pressed;
         self.events_loop.poll_events(|event| match event {
             glutin::Event::WindowEvent { event, .. } => match event {
                 glutin::WindowEvent::Closed => exit(0),
@@ -381,31 +829,66 @@ impl GLApp {
                     }
                 }
                 glutin::WindowEvent::Resized(w, h) => gl_window.resize(w, h),
+                glutin::WindowEvent::CursorMoved { position, .. } => {
+                    let height = gl_window.window().get_inner_size().map_or(0, |size| size.1);
+                    *mouse_position = (position.0 as f32, height as f32 - position.1 as f32);
+                    if *mouse_pressed {
+                        *mouse_drag_position = *mouse_position;
+                    }
+                }
+                glutin::WindowEvent::MouseInput { state, .. } => {
+                    *mouse_pressed = state == glutin::ElementState::Pressed;
+                    if *mouse_pressed {
+                        *mouse_press_position = *mouse_position;
+                        *mouse_drag_position = *mouse_position;
+                    }
+                }
                 _ => (),
             },
             _ => (),
         });
     }
 
+    // Shows the frame and clears the depth buffer for the next one. Depth is
+    // kept across renders within a frame so that shapes drawn after filter()
+    // or a feedback step are still hidden by those drawn before.
     pub fn swap_buffers(&mut self) {
+        if let Some(ref canvas) = self.canvas {
+            canvas.blit_to_window();
+        }
         self.gl_window.swap_buffers().unwrap();
+        clear_depth();
     }
 }
 
 // vertices are stored as:
-// vertex, color
+// vertex, uv, color, normal
 // Khronos advise to use 4-byte alignment for vertex attributes
 // vertex is xyz as 3 GLfloat (12 bytes)
 // uv is uv as 2 GLfloat (8 bytes)
 // color is rgba as 4 GLfloat (16 bytes)
-// the stride is therefore 9 GLfloat (36 bytes)
-const VBO_STRIDE_N: usize = 9;
-pub fn append_data(vertex_data: &[f32], index_data: &[u32]) {
+// normal is xyz as 3 GLfloat (12 bytes)
+// the stride is therefore 12 GLfloat (48 bytes)
+pub const VBO_STRIDE_N: usize = 12;
+// Fills drawn with normal_material() are coloured by their normals here, while
+// strokes keep the stroke colour
+pub fn append_data(vertex_data: &[f32], index_data: &[u32], is_stroke: bool) {
+    let normal_material = !is_stroke && SKETCH.lock().unwrap().normal_material;
     let total_vertices_before;
     {
         let mut vertices = VERTICES.lock().unwrap();
         total_vertices_before = vertices.len() / VBO_STRIDE_N;
+        let start = vertices.len();
         vertices.extend_from_slice(vertex_data);
+        if normal_material {
+            // map the normal from [-1, 1] to a colour in [0, 1]
+            for vertex in vertices[start..].chunks_mut(VBO_STRIDE_N) {
+                for i in 0..3 {
+                    vertex[5 + i] = 0.5 * (vertex[9 + i] + 1.0);
+                }
+                vertex[8] = 1.0;
+            }
+        }
     }
     let mut indices = INDICES.lock().unwrap();
     if indices.is_empty() {
@@ -438,16 +921,78 @@ fn drain_data() -> (Vec<GLfloat>, Vec<GLuint>) {
     (vertex_data, index_data)
 }
 
-pub fn append_shape(shader_program: GLuint, n_triangles: u32) {
-    let index_byte_offset = *INDEX_BYTES_OFFSET.lock().unwrap();
-    let mut gl_shapes = GL_SHAPES.lock().unwrap();
-    gl_shapes.push(GLShape {
+// Records the shape whose data was last appended along with how it is to be
+// blended, depth tested, lit and painted. Strokes and fills with
+// normal_material() are never lit, and strokes never have gradients.
+pub fn append_shape(
+    shader_program: GLuint,
+    vertex_data: &[f32],
+    is_stroke: bool,
+    uv_edges: UvEdges,
+) {
+    let (blend_mode, depth_test, depth_mask, depth_sort, lights, material, gradient, texture) = {
+        let sketch = SKETCH.lock().unwrap();
+        let (lights, material) =
+            if is_stroke || sketch.normal_material || sketch.lights.is_empty() {
+                (None, None)
+            } else {
+                (Some(sketch.lights.clone()), Some(sketch.material.clone()))
+            };
+        let (gradient, texture) = if is_stroke {
+            (None, None)
+        } else {
+            (sketch.fill_gradient.clone(), sketch.fill_texture)
+        };
+        (
+            sketch.blend_mode,
+            sketch.depth_test(),
+            sketch.depth_mask,
+            sketch.depth_sort,
+            lights,
+            material,
+            gradient,
+            texture,
+        )
+    };
+    let uv_edges = if *SMOOTH_UV_EDGES.lock().unwrap() {
+        uv_edges
+    } else {
+        UvEdges::None
+    };
+
+    // translucency only changes how shapes are depth tested. Shapes with
+    // edges smoothed in the fragment shader fade out there.
+    let translucent = depth_test && (depth_mask || depth_sort)
+        && (uv_edges != UvEdges::None
+            || vertex_data
+                .chunks(VBO_STRIDE_N)
+                .any(|vertex| vertex[8] < 1.0)
+            || gradient.as_ref().map_or(false, |g| g.is_translucent()));
+    let sort_depth = if translucent && depth_sort {
+        let n_vertices = vertex_data.len() / VBO_STRIDE_N;
+        let sum: f32 = vertex_data.chunks(VBO_STRIDE_N).map(|vertex| vertex[2]).sum();
+        Some(sum / n_vertices as f32)
+    } else {
+        None
+    };
+
+    let n_indices = INDICES.lock().unwrap().len() as u32;
+    let mut next_shape_index = NEXT_SHAPE_INDEX.lock().unwrap();
+    GL_SHAPES.lock().unwrap().push(GLShape {
         shader_program,
-        index_byte_offset,
-        n_triangles,
+        first_index: *next_shape_index,
+        n_indices: n_indices - *next_shape_index,
+        blend_mode,
+        depth_test,
+        depth_mask: depth_mask && !translucent,
+        sort_depth,
+        lights,
+        material,
+        gradient,
+        texture,
+        uv_edges,
     });
-    *INDEX_BYTES_OFFSET.lock().unwrap() +=
-        ((n_triangles + 2) * size_of::<GLuint>() as u32) as GLuint;
+    *next_shape_index = n_indices;
 }
 
 fn drain_shapes() -> Vec<GLShape> {
@@ -458,12 +1003,299 @@ fn drain_shapes() -> Vec<GLShape> {
 
 fn drain() -> ((Vec<GLfloat>, Vec<u32>), Vec<GLShape>) {
     let tuple = (drain_data(), drain_shapes());
-    *INDEX_BYTES_OFFSET.lock().unwrap() = 0;
+    *NEXT_SHAPE_INDEX.lock().unwrap() = 0;
     tuple
 }
 
+// Throws away the shapes drawn since the last render, such as when the
+// background is about to cover them
+pub fn discard() {
+    drain();
+}
+
+// Orders the shapes for drawing and merges neighbours in the index buffer that
+// are blended, depth tested, lit and painted the same way. Degenerate triangles
+// join the shapes so the indices between them can be drawn too.
+fn batch_shapes(shapes: Vec<GLShape>) -> Vec<GLBatch> {
+    let (mut sorted, unsorted): (Vec<GLShape>, Vec<GLShape>) =
+        shapes.into_iter().partition(|shape| shape.sort_depth.is_some());
+    // view space looks along -z so the furthest shapes have the lowest z
+    sorted.sort_by(|a, b| {
+        a.sort_depth
+            .partial_cmp(&b.sort_depth)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let mut batches: Vec<GLBatch> = Vec::new();
+    for shape in unsorted.into_iter().chain(sorted.into_iter()) {
+        if let Some(batch) = batches.last_mut() {
+            if batch.first_index + batch.n_indices == shape.first_index
+                && batch.blend_mode == shape.blend_mode
+                && batch.depth_test == shape.depth_test
+                && batch.depth_mask == shape.depth_mask
+                && same_lights(&batch.lights, &shape.lights)
+                && batch.material == shape.material
+                && batch.gradient == shape.gradient
+                && batch.texture == shape.texture
+                && batch.uv_edges == shape.uv_edges
+            {
+                batch.n_indices += shape.n_indices;
+                continue;
+            }
+        }
+        batches.push(GLBatch {
+            first_index: shape.first_index,
+            n_indices: shape.n_indices,
+            blend_mode: shape.blend_mode,
+            depth_test: shape.depth_test,
+            depth_mask: shape.depth_mask,
+            lights: shape.lights,
+            material: shape.material,
+            gradient: shape.gradient,
+            texture: shape.texture,
+            uv_edges: shape.uv_edges,
+        });
+    }
+    batches
+}
+
+// Uploads the material of a batch of lit shapes, whose ambient colour is their
+// vertex colour unless it was set
+fn set_material(program: GLuint, material: &Material) {
+    unsafe {
+        gl::Uniform1i(
+            uniform_location(program, "has_ambient"),
+            material.ambient.is_some() as GLint,
+        );
+        if let Some(ref ambient) = material.ambient {
+            gl::Uniform3fv(
+                uniform_location(program, "material_ambient"),
+                1,
+                ambient.as_slice().as_ptr(),
+            );
+        }
+        gl::Uniform3fv(
+            uniform_location(program, "material_specular"),
+            1,
+            material.specular.as_slice().as_ptr(),
+        );
+        gl::Uniform3fv(
+            uniform_location(program, "material_emissive"),
+            1,
+            material.emissive.as_slice().as_ptr(),
+        );
+        gl::Uniform1f(uniform_location(program, "material_shininess"), material.shininess);
+    }
+}
+
+// Shapes drawn between changes to the lights share them, so comparing pointers
+// usually avoids comparing the lights themselves
+fn same_lights(a: &Option<Arc<Vec<Light>>>, b: &Option<Arc<Vec<Light>>>) -> bool {
+    match (a, b) {
+        (&Some(ref a), &Some(ref b)) => Arc::ptr_eq(a, b) || a == b,
+        (&None, &None) => true,
+        _ => false,
+    }
+}
+
+// Sets the GL blend equation and factors following Processing's blendMode()
+fn set_blend_mode(mode: BlendMode) {
+    let (equation, source, destination) = match mode {
+        BlendMode::Blend => (gl::FUNC_ADD, gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA),
+        BlendMode::Add => (gl::FUNC_ADD, gl::SRC_ALPHA, gl::ONE),
+        BlendMode::Subtract => (gl::FUNC_REVERSE_SUBTRACT, gl::SRC_ALPHA, gl::ONE),
+        BlendMode::Multiply => (gl::FUNC_ADD, gl::ZERO, gl::SRC_COLOR),
+        BlendMode::Screen => (gl::FUNC_ADD, gl::ONE_MINUS_DST_COLOR, gl::ONE),
+        // the factors are ignored by MAX and MIN
+        BlendMode::Lightest => (gl::MAX, gl::ONE, gl::ONE),
+        BlendMode::Darkest => (gl::MIN, gl::ONE, gl::ONE),
+        BlendMode::Exclusion => (
+            gl::FUNC_ADD,
+            gl::ONE_MINUS_DST_COLOR,
+            gl::ONE_MINUS_SRC_COLOR,
+        ),
+        BlendMode::Replace => (gl::FUNC_ADD, gl::ONE, gl::ZERO),
+    };
+    unsafe {
+        gl::BlendEquation(equation);
+        gl::BlendFunc(source, destination);
+    }
+}
+
+fn uniform_location(program: GLuint, name: &str) -> GLint {
+    let name = CString::new(name).unwrap();
+    unsafe { gl::GetUniformLocation(program, name.as_ptr()) }
+}
+
+// Uploads the lights to the uniforms of the lit shader program, which must be
+// in use
+fn set_lights(program: GLuint, lights: &[Light]) {
+    let n_lights = lights.len().min(MAX_LIGHTS);
+    let mut kinds: Vec<GLint> = Vec::with_capacity(n_lights);
+    let mut colors: Vec<GLfloat> = Vec::with_capacity(3 * n_lights);
+    let mut speculars: Vec<GLfloat> = Vec::with_capacity(3 * n_lights);
+    let mut positions: Vec<GLfloat> = Vec::with_capacity(3 * n_lights);
+    let mut directions: Vec<GLfloat> = Vec::with_capacity(3 * n_lights);
+    let mut falloffs: Vec<GLfloat> = Vec::with_capacity(3 * n_lights);
+    let mut spots: Vec<GLfloat> = Vec::with_capacity(2 * n_lights);
+    for light in &lights[..n_lights] {
+        kinds.push(match light.kind {
+            LightKind::Ambient => 0,
+            LightKind::Directional => 1,
+            LightKind::Point => 2,
+            LightKind::Spot => 3,
+        });
+        colors.extend_from_slice(light.color.as_slice());
+        speculars.extend_from_slice(light.specular.as_slice());
+        positions.extend_from_slice(light.position.coords.as_slice());
+        directions.extend_from_slice(light.direction.as_slice());
+        falloffs.extend_from_slice(light.falloff.as_slice());
+        spots.push(light.spot_cos);
+        spots.push(light.concentration);
+    }
+    let n = n_lights as GLsizei;
+    unsafe {
+        gl::Uniform1i(uniform_location(program, "light_count"), n);
+        gl::Uniform1iv(uniform_location(program, "light_kind"), n, kinds.as_ptr());
+        gl::Uniform3fv(uniform_location(program, "light_color"), n, colors.as_ptr());
+        gl::Uniform3fv(uniform_location(program, "light_specular"), n, speculars.as_ptr());
+        gl::Uniform3fv(uniform_location(program, "light_position"), n, positions.as_ptr());
+        gl::Uniform3fv(uniform_location(program, "light_direction"), n, directions.as_ptr());
+        gl::Uniform3fv(uniform_location(program, "light_falloff"), n, falloffs.as_ptr());
+        gl::Uniform2fv(uniform_location(program, "light_spot"), n, spots.as_ptr());
+    }
+}
+
+// Uploads the float uniforms set on a custom shader, which must be in use
+fn set_shader_uniforms(program: GLuint, shader: &FilterShader) {
+    for &(ref name, ref value) in &shader.uniforms {
+        let location = uniform_location(program, name);
+        unsafe {
+            match value.len() {
+                1 => gl::Uniform1fv(location, 1, value.as_ptr()),
+                2 => gl::Uniform2fv(location, 1, value.as_ptr()),
+                3 => gl::Uniform3fv(location, 1, value.as_ptr()),
+                4 => gl::Uniform4fv(location, 1, value.as_ptr()),
+                _ => (),
+            }
+        }
+    }
+}
+
+// Uploads the gradient, or that there is none, to the uniforms of a shader
+// program that paints with it, which must be in use
+fn set_gradient(program: GLuint, gradient: &Option<Gradient>) {
+    let gradient = match *gradient {
+        Some(ref gradient) => gradient,
+        None => {
+            unsafe {
+                gl::Uniform1i(uniform_location(program, "gradient_kind"), 0);
+            }
+            return;
+        }
+    };
+    let (kind, geometry) = match gradient.kind {
+        GradientKind::Linear { start, end } => (1, [start.x, start.y, end.x, end.y]),
+        GradientKind::Radial { center, radius } => (2, [center.x, center.y, radius, 0.0]),
+        GradientKind::Conic { center, angle } => (3, [center.x, center.y, angle, 0.0]),
+    };
+    let n_stops = gradient.stops.len().min(MAX_GRADIENT_STOPS);
+    let mut offsets: Vec<GLfloat> = Vec::with_capacity(n_stops);
+    let mut colors: Vec<GLfloat> = Vec::with_capacity(4 * n_stops);
+    for stop in &gradient.stops[..n_stops] {
+        offsets.push(stop.0);
+        colors.extend_from_slice(stop.1.as_slice());
+    }
+    let n = n_stops as GLsizei;
+    unsafe {
+        gl::Uniform1i(uniform_location(program, "gradient_kind"), kind);
+        gl::Uniform4fv(uniform_location(program, "gradient_geometry"), 1, geometry.as_ptr());
+        gl::Uniform1i(uniform_location(program, "gradient_stop_count"), n);
+        gl::Uniform1fv(uniform_location(program, "gradient_offset"), n, offsets.as_ptr());
+        gl::Uniform4fv(uniform_location(program, "gradient_color"), n, colors.as_ptr());
+    }
+}
+
+pub fn create_feedback_buffer(id: u32, w: u32, h: u32) {
+    channel::push(Box::new(move || {
+        GLAPP.with(|handle| {
+            if let Some(ref mut glapp) = *handle.borrow_mut() {
+                glapp.create_feedback_buffer(id, w, h);
+            }
+        });
+    }));
+}
+
+pub fn step_feedback_buffer(id: u32, shader: FilterShader) {
+    channel::push(Box::new(move || {
+        GLAPP.with(|handle| {
+            if let Some(ref mut glapp) = *handle.borrow_mut() {
+                glapp.step_feedback_buffer(id, &shader);
+            }
+        });
+    }));
+}
+
+pub fn display_feedback_buffer(id: u32) {
+    channel::push(Box::new(move || {
+        GLAPP.with(|handle| {
+            if let Some(ref mut glapp) = *handle.borrow_mut() {
+                glapp.display_feedback_buffer(id);
+            }
+        });
+    }));
+}
+
+pub fn delete_feedback_buffer(id: u32) {
+    channel::push(Box::new(move || {
+        GLAPP.with(|handle| {
+            if let Some(ref mut glapp) = *handle.borrow_mut() {
+                glapp.feedback_buffers.remove(&id);
+                if let Some(ref canvas) = glapp.canvas {
+                    canvas.bind();
+                }
+            }
+        });
+    }));
+}
+
+fn get_feedback_texture_gl(id: u32) -> Option<GLuint> {
+    let mut texture = None;
+    GLAPP.with(|handle| {
+        if let Some(ref glapp) = *handle.borrow() {
+            texture = glapp.feedback_texture(id);
+        }
+    });
+    texture
+}
+
+// Draws a frame of a shader sketch, with the fragment shader wrapped by
+// shader_sketch::wrap_fragment_shader()
+pub fn draw_shader_sketch(fragment_shader: String) {
+    channel::push(Box::new(move || {
+        GLAPP.with(|handle| {
+            if let Some(ref mut glapp) = *handle.borrow_mut() {
+                glapp.draw_shader_sketch(&fragment_shader);
+            }
+        });
+    }));
+}
+
+// Runs the filter over the canvas once everything pushed before it is drawn
+pub fn apply_filter(filter: Filter) {
+    channel::push(Box::new(move || {
+        GLAPP.with(|handle| {
+            if let Some(ref mut glapp) = *handle.borrow_mut() {
+                glapp.filter(&filter);
+            }
+        });
+    }));
+}
+
 pub fn render() {
-    let ((vertex_data, index_data), _) = drain();
+    let ((vertex_data, index_data), shapes) = drain();
+    let batches = batch_shapes(shapes);
+    let projection: Matrix4<f32> = *SKETCH.lock().unwrap().projection.matrix();
     channel::push(Box::new(move || {
         // prepare next frame and get objects for this frame
         let mut objects = (0, 0, 0, 0);
@@ -482,15 +1314,63 @@ pub fn render() {
         // draw
         let vao = objects.0;
         let default_shader_program = get_default_shader_program_gl();
+        let lit_shader_program = get_lit_shader_program_gl();
         unsafe {
-            gl::UseProgram(default_shader_program);
+            for &program in &[default_shader_program, lit_shader_program] {
+                gl::UseProgram(program);
+                // nalgebra matrices are column-major as GL expects
+                gl::UniformMatrix4fv(
+                    uniform_location(program, "projection"),
+                    1,
+                    gl::FALSE,
+                    projection.as_slice().as_ptr(),
+                );
+            }
             gl::BindVertexArray(vao);
-            gl::DrawElements(
-                gl::TRIANGLE_STRIP,
-                n_indices as GLsizei,
-                gl::UNSIGNED_INT,
-                ptr::null(),
-            );
+            for batch in &batches {
+                set_blend_mode(batch.blend_mode);
+                let program = match batch.lights {
+                    Some(_) => lit_shader_program,
+                    None => default_shader_program,
+                };
+                gl::UseProgram(program);
+                if let Some(ref lights) = batch.lights {
+                    set_lights(program, lights);
+                }
+                if let Some(ref material) = batch.material {
+                    set_material(program, material);
+                }
+                set_gradient(program, &batch.gradient);
+                let texture = batch.texture.and_then(get_feedback_texture_gl);
+                gl::Uniform1i(
+                    uniform_location(program, "has_fill_texture"),
+                    texture.is_some() as GLint,
+                );
+                gl::Uniform1i(uniform_location(program, "fill_texture"), 0);
+                gl::ActiveTexture(gl::TEXTURE0);
+                gl::BindTexture(gl::TEXTURE_2D, texture.unwrap_or(0));
+                gl::Uniform1i(
+                    uniform_location(program, "uv_edges"),
+                    match batch.uv_edges {
+                        UvEdges::None => 0,
+                        UvEdges::Rectangle => 1,
+                        UvEdges::Ellipse => 2,
+                        UvEdges::Stroke => 3,
+                    },
+                );
+                if batch.depth_test {
+                    gl::Enable(gl::DEPTH_TEST);
+                } else {
+                    gl::Disable(gl::DEPTH_TEST);
+                }
+                gl::DepthMask(if batch.depth_mask { gl::TRUE } else { gl::FALSE });
+                gl::DrawElements(
+                    gl::TRIANGLE_STRIP,
+                    batch.n_indices as GLsizei,
+                    gl::UNSIGNED_INT,
+                    (batch.first_index as usize * size_of::<GLuint>()) as *const c_void,
+                );
+            }
         }
 
         // cleanup
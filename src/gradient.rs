@@ -0,0 +1,119 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use color::{color, Color};
+use sketch::SKETCH;
+
+use na::{Point2, Vector4};
+
+use std::cmp::Ordering;
+
+// The most colour stops a gradient can have, matching MAX_GRADIENT_STOPS in
+// the fragment shaders. Any more are ignored.
+pub const MAX_GRADIENT_STOPS: usize = 8;
+
+// Where a gradient runs, in the texture coordinates of the shapes it fills.
+// These go from (0, 0) at the bottom-left to (1, 1) at the top-right of a
+// shape's bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientKind {
+    // along the line from start at offset 0 to end at offset 1
+    Linear {
+        start: Point2<f32>,
+        end: Point2<f32>,
+    },
+    // out from the centre at offset 0 to the radius at offset 1
+    Radial {
+        center: Point2<f32>,
+        radius: f32,
+    },
+    // anticlockwise around the centre, from the angle in radians at offset 0
+    // to a full turn at offset 1
+    Conic {
+        center: Point2<f32>,
+        angle: f32,
+    },
+}
+
+// A paint for fills that blends between colour stops, each at an offset from
+// 0 to 1 along the gradient. Before the first stop and after the last, their
+// colours continue.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    // sorted by offset, with colours as normalized RGBA
+    pub stops: Vec<(f32, Color)>,
+}
+
+impl Gradient {
+    pub fn linear(start: Point2<f32>, end: Point2<f32>) -> Gradient {
+        Gradient::new(GradientKind::Linear { start, end })
+    }
+
+    pub fn radial(center: Point2<f32>, radius: f32) -> Gradient {
+        Gradient::new(GradientKind::Radial { center, radius })
+    }
+
+    pub fn conic(center: Point2<f32>, angle: f32) -> Gradient {
+        Gradient::new(GradientKind::Conic { center, angle })
+    }
+
+    pub fn new(kind: GradientKind) -> Gradient {
+        Gradient {
+            kind,
+            stops: Vec::new(),
+        }
+    }
+
+    // Adds a colour stop, interpreting the colour with the current colour
+    // mode. Stops at the same offset make a hard edge.
+    pub fn stop<C: Into<Color>>(mut self, offset: f32, c: C) -> Gradient {
+        let index = self
+            .stops
+            .iter()
+            .position(|stop| stop.0.partial_cmp(&offset) == Some(Ordering::Greater))
+            .unwrap_or_else(|| self.stops.len());
+        self.stops.insert(index, (offset, color(c)));
+        self
+    }
+
+    pub fn is_translucent(&self) -> bool {
+        self.stops
+            .iter()
+            .take(MAX_GRADIENT_STOPS)
+            .any(|stop| stop.1.w < 1.0)
+    }
+}
+
+// Fills shapes drawn afterwards with the gradient instead of a flat colour,
+// until fill(), no_fill() or fill_feedback(). Fills that are lit use the
+// gradient as their diffuse colour.
+pub fn fill_gradient(gradient: &Gradient) {
+    let mut sketch = SKETCH.lock().unwrap();
+    // the shapes' vertex colours multiply the gradient
+    sketch.fill = Vector4::new(1.0, 1.0, 1.0, 1.0).into();
+    sketch.fill_gradient = Some(gradient.clone());
+    sketch.fill_texture = None;
+    sketch.normal_material = false;
+}
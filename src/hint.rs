@@ -0,0 +1,56 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use sketch::SKETCH;
+
+// Switches for how shapes are depth tested, as with Processing's hint()
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hint {
+    // shapes are drawn over everything drawn before them, in order
+    DisableDepthTest,
+    // shapes are hidden behind nearer ones drawn before them, the default for
+    // the P3D renderer
+    EnableDepthTest,
+    // shapes do not hide the ones drawn after them
+    DisableDepthMask,
+    EnableDepthMask,
+    // translucent shapes are drawn after all opaque ones, furthest first
+    EnableDepthSort,
+    // translucent shapes are drawn in order, the default
+    DisableDepthSort,
+}
+
+// Translucent shapes never hide the ones drawn after them, whatever the depth
+// mask, as anything behind them must still show through
+pub fn hint(which: Hint) {
+    let mut sketch = SKETCH.lock().unwrap();
+    match which {
+        Hint::DisableDepthTest => sketch.depth_test_hint = Some(false),
+        Hint::EnableDepthTest => sketch.depth_test_hint = Some(true),
+        Hint::DisableDepthMask => sketch.depth_mask = false,
+        Hint::EnableDepthMask => sketch.depth_mask = true,
+        Hint::EnableDepthSort => sketch.depth_sort = true,
+        Hint::DisableDepthSort => sketch.depth_sort = false,
+    }
+}
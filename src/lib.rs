@@ -31,31 +31,67 @@ extern crate gl;
 extern crate lazy_static;
 extern crate nalgebra as na;
 
+mod blend;
+mod camera;
+mod canvas;
 mod channel;
 mod color;
+mod css_colors;
+mod curve;
+mod custom_shape;
 mod ellipse;
+mod feedback;
+mod filter;
 mod glapp;
+mod gradient;
+mod hint;
+mod light;
 mod line;
+mod material;
+mod mesh;
+mod model;
 mod point;
+mod quad;
 mod random;
 mod rectangle;
 mod shader;
+mod shader_sketch;
 mod shape;
 mod sketch;
+mod solid;
+mod stroke;
+mod tessellation;
 mod transformation;
 mod triangle;
 mod utils;
 
+pub use blend::*;
+pub use camera::*;
 pub use color::*;
+pub use curve::*;
+pub use custom_shape::*;
 pub use ellipse::*;
-pub use glapp::size;
+pub use feedback::*;
+pub use filter::*;
+pub use glapp::{no_smooth, size, size_with_renderer, smooth};
+pub use gradient::*;
+pub use hint::*;
+pub use light::*;
 pub use line::*;
+pub use material::*;
+pub use mesh::*;
+pub use model::*;
 pub use point::*;
+pub use quad::*;
 pub use random::*;
 pub use rectangle::*;
 pub use shader::*;
+pub use shader_sketch::*;
 pub use shape::*;
 pub use sketch::*;
+pub use solid::*;
+pub use stroke::*;
+pub use tessellation::WindingRule;
 pub use transformation::*;
 pub use triangle::*;
 pub use utils::*;
@@ -89,7 +125,7 @@ pub fn run_sketch(setup: fn(), draw: fn(), log: bool) {
                 counter.tick(&time);
             }
             transformation::reset();
-            color::draw_background();
+            light::no_lights();
             draw();
 
             glapp::render();
@@ -0,0 +1,170 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use color::Color;
+use sketch;
+use sketch::SKETCH;
+use transformation::get_transformation;
+
+use na::{Point3, Vector3, Vector4};
+
+use std::sync::Arc;
+
+// The most lights that can be active at once, as in Processing. Lights added
+// beyond this are ignored.
+pub const MAX_LIGHTS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+    Ambient,
+    Directional,
+    Point,
+    Spot,
+}
+
+// A light as seen from the eye, with its position and direction in view space
+// so that they are fixed at the transformation that was current when it was
+// added
+#[derive(Clone, Debug, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: Vector3<f32>,
+    // the colour of the highlights it makes on specular materials
+    pub specular: Vector3<f32>,
+    pub position: Point3<f32>,
+    pub direction: Vector3<f32>,
+    // constant, linear and quadratic attenuation with distance
+    pub falloff: Vector3<f32>,
+    // the cosine of the spot cone's half angle and the spot's concentration
+    pub spot_cos: f32,
+    pub concentration: f32,
+}
+
+fn add_light<C: Into<Color>>(
+    kind: LightKind,
+    color: C,
+    position: Point3<f32>,
+    direction: Vector3<f32>,
+    spot_angle: f32,
+    concentration: f32,
+) {
+    let mut sketch = sketch::get_sketch();
+    if sketch.lights.len() >= MAX_LIGHTS {
+        return;
+    }
+    let color: Color = color.into();
+    let transform = get_transformation(&sketch.transformation);
+    let light = Light {
+        kind,
+        color: Vector3::new(color.x, color.y, color.z),
+        specular: sketch.light_specular,
+        position: transform * position,
+        direction: (transform * direction).normalize(),
+        falloff: sketch.light_falloff,
+        spot_cos: spot_angle.cos(),
+        concentration,
+    };
+    Arc::make_mut(&mut sketch.lights).push(light);
+}
+
+// Processing's default lights: a grey ambient light and a grey directional
+// light shining along -z, away from the default eye, whatever the colour mode
+pub fn lights() {
+    let gray = Vector4::new(0.5, 0.5, 0.5, 1.0);
+    light_falloff(1.0, 0.0, 0.0);
+    light_specular(Vector4::zeros());
+    ambient_light(gray);
+    directional_light(gray, Vector3::new(0.0, 0.0, -1.0));
+}
+
+// Removes all lights so that shapes are drawn unlit. This happens before each
+// call to draw().
+pub fn no_lights() {
+    let mut sketch = SKETCH.lock().unwrap();
+    if !sketch.lights.is_empty() {
+        sketch.lights = Arc::new(Vec::new());
+    }
+    sketch.light_falloff = Vector3::new(1.0, 0.0, 0.0);
+    sketch.light_specular = Vector3::zeros();
+}
+
+pub fn ambient_light<C: Into<Color>>(color: C) {
+    add_light(
+        LightKind::Ambient,
+        color,
+        Point3::origin(),
+        Vector3::z(),
+        0.0,
+        0.0,
+    );
+}
+
+pub fn directional_light<C: Into<Color>>(color: C, direction: Vector3<f32>) {
+    add_light(
+        LightKind::Directional,
+        color,
+        Point3::origin(),
+        direction,
+        0.0,
+        0.0,
+    );
+}
+
+pub fn point_light<C: Into<Color>>(color: C, position: Point3<f32>) {
+    add_light(LightKind::Point, color, position, Vector3::z(), 0.0, 0.0);
+}
+
+// A point light limited to a cone around the direction, with the angle in
+// radians between the direction and the edge of the cone. Higher
+// concentrations focus more of the light towards the direction.
+pub fn spot_light<C: Into<Color>>(
+    color: C,
+    position: Point3<f32>,
+    direction: Vector3<f32>,
+    angle: f32,
+    concentration: f32,
+) {
+    add_light(
+        LightKind::Spot,
+        color,
+        position,
+        direction,
+        angle,
+        concentration,
+    );
+}
+
+// Sets how point and spot lights added afterwards fade with distance d, by
+// 1 / (constant + linear * d + quadratic * d * d)
+pub fn light_falloff(constant: f32, linear: f32, quadratic: f32) {
+    SKETCH.lock().unwrap().light_falloff = Vector3::new(constant, linear, quadratic);
+}
+
+// Sets the colour of the highlights made by lights added afterwards, which is
+// black by default so that there are none
+pub fn light_specular<C: Into<Color>>(color: C) {
+    let mut sketch = SKETCH.lock().unwrap();
+    let color: Color = color.into();
+    sketch.light_specular = Vector3::new(color.x, color.y, color.z);
+}
@@ -22,19 +22,10 @@
  * SOFTWARE.
  */
 
-use point::*;
-use rectangle::Rectangle;
-use shape::Shape;
-use sketch::get_stroke_weight;
+use stroke::polyline;
 
 use na::Point3;
 
 pub fn line(start: Point3<f32>, end: Point3<f32>) {
-    Rectangle::new(start.clone(), end.clone(), true, true).draw();
-    // Note: this is a small optimization for small stroke weights to not draw points
-    let width = get_stroke_weight();
-    if width > 4 {
-        point(start);
-        point(end);
-    }
+    polyline(&[start, end], false);
 }
@@ -35,7 +35,6 @@ const HEIGHT: u32 = 800;
 
 fn setup() {
     size(WIDTH, HEIGHT);
-    background(0.2);
 
     let mut ps: Vec<Point3<f32>> = Vec::with_capacity(N_OBJECTS);
     for _ in 0..N_OBJECTS {
@@ -51,9 +50,9 @@ fn setup() {
 }
 
 fn draw() {
-    stroke((1.0, 1.0, 1.0, 0.3));
+    background(0.2);
+    no_stroke();
     fill((1.0, 1.0, 1.0, 0.3));
-    stroke_weight(12);
 
     unsafe {
         if let Some(ref ps) = POINTS {
@@ -0,0 +1,90 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use color::Color;
+use sketch::SKETCH;
+
+use na::Vector3;
+
+// How lit fills reflect light, as with Processing's ambient(), specular(),
+// emissive() and shininess()
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    // the colour reflected from ambient lights, which is the fill if None
+    pub ambient: Option<Vector3<f32>>,
+    // the colour of highlights, reflected from the specular colour of lights
+    pub specular: Vector3<f32>,
+    // the colour given off without any light
+    pub emissive: Vector3<f32>,
+    // higher values give smaller, sharper highlights
+    pub shininess: f32,
+}
+
+impl Material {
+    pub fn new() -> Material {
+        Material {
+            ambient: None,
+            specular: Vector3::zeros(),
+            emissive: Vector3::zeros(),
+            shininess: 1.0,
+        }
+    }
+}
+
+fn rgb<C: Into<Color>>(color: C) -> Vector3<f32> {
+    let color: Color = color.into();
+    Vector3::new(color.x, color.y, color.z)
+}
+
+pub fn ambient_material<C: Into<Color>>(color: C) {
+    let ambient = rgb(color);
+    let mut sketch = SKETCH.lock().unwrap();
+    sketch.material.ambient = Some(ambient);
+    sketch.normal_material = false;
+}
+
+pub fn specular_material<C: Into<Color>>(color: C) {
+    let specular = rgb(color);
+    let mut sketch = SKETCH.lock().unwrap();
+    sketch.material.specular = specular;
+    sketch.normal_material = false;
+}
+
+pub fn emissive_material<C: Into<Color>>(color: C) {
+    let emissive = rgb(color);
+    let mut sketch = SKETCH.lock().unwrap();
+    sketch.material.emissive = emissive;
+    sketch.normal_material = false;
+}
+
+pub fn shininess(shininess: f32) {
+    SKETCH.lock().unwrap().material.shininess = shininess.max(1.0);
+}
+
+// Fills are coloured by the direction they face as seen from the eye, with x,
+// y and z mapped to red, green and blue, and are not lit. Setting the fill or
+// another material restores the usual colouring.
+pub fn normal_material() {
+    SKETCH.lock().unwrap().normal_material = true;
+}
@@ -0,0 +1,165 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use color::Color;
+use glapp::VBO_STRIDE_N;
+use shape;
+use shape::*;
+
+use na::{Matrix4, Point3, Transform3, Vector3};
+
+// A variable-sized triangle strip for geometry whose vertex count is only known
+// at runtime, such as custom shapes. Disjoint pieces are joined with degenerate
+// triangles in the same way as append_data() joins shapes.
+pub struct Mesh {
+    vertex_data: Vec<f32>,
+    index_data: Vec<u32>,
+    transform: Transform3<f32>,
+    // None if the normals are not needed, leaving them facing +z
+    normal_transform: Option<Matrix4<f32>>,
+    is_stroke: bool,
+}
+
+impl Mesh {
+    pub fn new(
+        transform: Transform3<f32>,
+        normal_transform: Option<Matrix4<f32>>,
+        is_stroke: bool,
+    ) -> Mesh {
+        Mesh {
+            vertex_data: Vec::new(),
+            index_data: Vec::new(),
+            transform,
+            normal_transform,
+            is_stroke,
+        }
+    }
+
+    pub fn n_vertices(&self) -> usize {
+        self.vertex_data.len() / VBO_STRIDE_N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index_data.len() < 3
+    }
+
+    // Transforms and appends a vertex facing along +z, returning its index
+    pub fn push_vertex(&mut self, p: &Point3<f32>, uv: &[f32], color: &Color) -> u32 {
+        self.push_vertex_normal(p, &Vector3::z(), uv, color)
+    }
+
+    // Transforms and appends a vertex with a surface normal, returning its index
+    pub fn push_vertex_normal(
+        &mut self,
+        p: &Point3<f32>,
+        n: &Vector3<f32>,
+        uv: &[f32],
+        color: &Color,
+    ) -> u32 {
+        let index = self.n_vertices();
+        let point = self.transform * *p;
+        let normal = match self.normal_transform {
+            Some(ref normal_transform) => transform_normal(normal_transform, n),
+            None => Vector3::z(),
+        };
+        self.vertex_data.extend_from_slice(&[0.0; VBO_STRIDE_N]);
+        assign_vertex_normal(
+            &point,
+            &normal,
+            uv,
+            color,
+            &mut self.vertex_data[index * VBO_STRIDE_N..],
+        );
+        index as u32
+    }
+
+    pub fn push_strip(&mut self, strip: &[u32]) {
+        if strip.len() < 3 {
+            return;
+        }
+        if let Some(&last) = self.index_data.last() {
+            self.index_data.push(last);
+            self.index_data.push(strip[0]);
+        }
+        self.index_data.extend_from_slice(strip);
+    }
+
+    pub fn push_triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.push_strip(&[a, b, c]);
+    }
+
+    // Appends a list of triangles where each consecutive three indices form one
+    pub fn push_triangles(&mut self, triangles: &[u32]) {
+        for triangle in triangles.chunks(3) {
+            if triangle.len() == 3 {
+                self.push_triangle(triangle[0], triangle[1], triangle[2]);
+            }
+        }
+    }
+}
+
+// Normals are transformed by the inverse transpose so that they stay
+// perpendicular to surfaces under non-uniform scaling
+pub fn normal_transformation(transform: &Transform3<f32>) -> Matrix4<f32> {
+    match transform.try_inverse() {
+        Some(inverse) => inverse.matrix().transpose(),
+        None => Matrix4::identity(),
+    }
+}
+
+pub fn transform_normal(normal_transform: &Matrix4<f32>, n: &Vector3<f32>) -> Vector3<f32> {
+    let normal = normal_transform * n.to_homogeneous();
+    Vector3::new(normal.x, normal.y, normal.z).normalize()
+}
+
+impl Shape for Mesh {
+    fn vertex_data(&self) -> &[f32] {
+        &self.vertex_data
+    }
+    fn index_data(&self) -> &[u32] {
+        &self.index_data
+    }
+    fn vertex_shader(&self) -> Option<String> {
+        None
+    }
+    fn fragment_shader(&self) -> Option<String> {
+        None
+    }
+    fn draw(&self) {
+        if self.is_empty() {
+            return;
+        }
+        shape::draw(self);
+    }
+    fn is_stroke(&self) -> bool {
+        self.is_stroke
+    }
+    fn uv_edges(&self) -> UvEdges {
+        if self.is_stroke {
+            UvEdges::Stroke
+        } else {
+            UvEdges::None
+        }
+    }
+}
@@ -0,0 +1,410 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use color::Color;
+use material::Material;
+use mesh::Mesh;
+use shape::Shape;
+use sketch;
+use tessellation::{project_to_plane, triangulate};
+use transformation::{get_normal_transformation, get_transformation};
+
+use na::{Point3, Vector3, Vector4};
+
+use std::collections::HashMap;
+use std::f32;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+struct ModelVertex {
+    position: Point3<f32>,
+    normal: Vector3<f32>,
+    uv: [f32; 2],
+}
+
+// The faces that use one of the materials from the MTL files, or none
+struct ModelGroup {
+    material_name: Option<String>,
+    vertices: Vec<ModelVertex>,
+    triangles: Vec<u32>,
+}
+
+// A material from an MTL file: the diffuse colour, which replaces the fill,
+// and the rest of the material
+#[derive(Clone)]
+struct ModelMaterial {
+    diffuse: Color,
+    material: Material,
+}
+
+// Triangles loaded from a Wavefront OBJ file with load_model(), to be drawn
+// with model()
+pub struct Model {
+    groups: Vec<ModelGroup>,
+    materials: HashMap<String, ModelMaterial>,
+}
+
+impl Model {
+    pub fn n_triangles(&self) -> usize {
+        self.groups
+            .iter()
+            .map(|group| group.triangles.len() / 3)
+            .sum()
+    }
+
+    fn group(&mut self, material_name: Option<String>) -> &mut ModelGroup {
+        let index = match self
+            .groups
+            .iter()
+            .position(|group| group.material_name == material_name)
+        {
+            Some(index) => index,
+            None => {
+                self.groups.push(ModelGroup {
+                    material_name,
+                    vertices: Vec::new(),
+                    triangles: Vec::new(),
+                });
+                self.groups.len() - 1
+            }
+        };
+        &mut self.groups[index]
+    }
+
+    // Centres the model on the origin and scales it so that its largest
+    // dimension is 1
+    fn normalize(&mut self) {
+        let mut min = Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        let mut max = Point3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for group in &self.groups {
+            for vertex in &group.vertices {
+                min = Point3::new(
+                    min.x.min(vertex.position.x),
+                    min.y.min(vertex.position.y),
+                    min.z.min(vertex.position.z),
+                );
+                max = Point3::new(
+                    max.x.max(vertex.position.x),
+                    max.y.max(vertex.position.y),
+                    max.z.max(vertex.position.z),
+                );
+            }
+        }
+        let size = max - min;
+        let largest = size.x.max(size.y).max(size.z);
+        if largest <= 0.0 {
+            return;
+        }
+        let center = min + 0.5 * size;
+        for group in &mut self.groups {
+            for vertex in &mut group.vertices {
+                vertex.position = Point3::origin() + (vertex.position - center) / largest;
+            }
+        }
+    }
+}
+
+fn invalid_data(path: &Path, line_number: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}:{}: {}", path.display(), line_number, message),
+    )
+}
+
+fn parse_floats(words: &[&str], n: usize, path: &Path, line_number: usize) -> io::Result<Vec<f32>> {
+    if words.len() < n {
+        return Err(invalid_data(path, line_number, "too few values"));
+    }
+    words[..n]
+        .iter()
+        .map(|word| {
+            word.parse::<f32>()
+                .map_err(|_| invalid_data(path, line_number, "invalid number"))
+        })
+        .collect()
+}
+
+// Resolves a 1-based OBJ index, which counts back from the end if negative,
+// to an index into the n_elements so far. Empty indices are None.
+fn parse_index(
+    word: Option<&str>,
+    n_elements: usize,
+    path: &Path,
+    line_number: usize,
+) -> io::Result<Option<usize>> {
+    let word = match word {
+        Some(word) if !word.is_empty() => word,
+        _ => return Ok(None),
+    };
+    let index = word
+        .parse::<i64>()
+        .map_err(|_| invalid_data(path, line_number, "invalid index"))?;
+    let resolved = if index < 0 {
+        n_elements as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved >= n_elements as i64 {
+        return Err(invalid_data(path, line_number, "index out of range"));
+    }
+    Ok(Some(resolved as usize))
+}
+
+// The normal of a planar polygon by Newell's method, which works for concave
+// polygons too
+fn face_normal(points: &[Point3<f32>]) -> Vector3<f32> {
+    let mut normal = Vector3::zeros();
+    for i in 0..points.len() {
+        let (a, b) = (&points[i], &points[(i + 1) % points.len()]);
+        normal.x += (a.y - b.y) * (a.z + b.z);
+        normal.y += (a.z - b.z) * (a.x + b.x);
+        normal.z += (a.x - b.x) * (a.y + b.y);
+    }
+    if normal.norm_squared() > 0.0 {
+        normal.normalize()
+    } else {
+        Vector3::z()
+    }
+}
+
+fn load_materials(path: &Path, materials: &mut HashMap<String, ModelMaterial>) -> io::Result<()> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut name: Option<String> = None;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() || words[0].starts_with('#') {
+            continue;
+        }
+        if words[0] == "newmtl" {
+            let new_name = words[1..].join(" ");
+            materials.insert(
+                new_name.clone(),
+                ModelMaterial {
+                    diffuse: Vector4::new(0.8, 0.8, 0.8, 1.0).into(),
+                    material: Material::new(),
+                },
+            );
+            name = Some(new_name);
+            continue;
+        }
+        let entry = match name {
+            Some(ref name) => materials.get_mut(name).unwrap(),
+            None => continue,
+        };
+        let line_number = i + 1;
+        match words[0] {
+            "Kd" => {
+                let v = parse_floats(&words[1..], 3, path, line_number)?;
+                let alpha = entry.diffuse.w;
+                entry.diffuse = Vector4::new(v[0], v[1], v[2], alpha).into();
+            }
+            "Ka" => {
+                let v = parse_floats(&words[1..], 3, path, line_number)?;
+                entry.material.ambient = Some(Vector3::new(v[0], v[1], v[2]));
+            }
+            "Ks" => {
+                let v = parse_floats(&words[1..], 3, path, line_number)?;
+                entry.material.specular = Vector3::new(v[0], v[1], v[2]);
+            }
+            "Ke" => {
+                let v = parse_floats(&words[1..], 3, path, line_number)?;
+                entry.material.emissive = Vector3::new(v[0], v[1], v[2]);
+            }
+            "Ns" => {
+                let v = parse_floats(&words[1..], 1, path, line_number)?;
+                entry.material.shininess = v[0].max(1.0);
+            }
+            "d" => {
+                let v = parse_floats(&words[1..], 1, path, line_number)?;
+                entry.diffuse.w = v[0];
+            }
+            "Tr" => {
+                let v = parse_floats(&words[1..], 1, path, line_number)?;
+                entry.diffuse.w = 1.0 - v[0];
+            }
+            // textures are not supported
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+// Loads the faces of a Wavefront OBJ file, along with the colours and
+// materials of any MTL files it uses, failing if any of those cannot be
+// loaded. Faces without normals are given the normal of their plane. If
+// normalize, the model is centred on the origin and scaled so that its largest
+// dimension is 1.
+pub fn load_model(path: &str, normalize: bool) -> io::Result<Model> {
+    let path = Path::new(path);
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut positions: Vec<Point3<f32>> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut normals: Vec<Vector3<f32>> = Vec::new();
+    let mut material_name: Option<String> = None;
+    let mut model = Model {
+        groups: Vec::new(),
+        materials: HashMap::new(),
+    };
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = i + 1;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() || words[0].starts_with('#') {
+            continue;
+        }
+        match words[0] {
+            "v" => {
+                let v = parse_floats(&words[1..], 3, path, line_number)?;
+                positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let u = parse_floats(&words[1..], 1, path, line_number)?;
+                let v = words
+                    .get(2)
+                    .and_then(|word| word.parse().ok())
+                    .unwrap_or(0.0);
+                uvs.push([u[0], v]);
+            }
+            "vn" => {
+                let v = parse_floats(&words[1..], 3, path, line_number)?;
+                normals.push(Vector3::new(v[0], v[1], v[2]).normalize());
+            }
+            "f" => {
+                let mut corners = Vec::with_capacity(words.len() - 1);
+                for word in &words[1..] {
+                    let mut indices = word.split('/');
+                    let position = parse_index(indices.next(), positions.len(), path, line_number)?;
+                    let uv = parse_index(indices.next(), uvs.len(), path, line_number)?;
+                    let normal = parse_index(indices.next(), normals.len(), path, line_number)?;
+                    match position {
+                        Some(position) => corners.push((position, uv, normal)),
+                        None => return Err(invalid_data(path, line_number, "missing position")),
+                    }
+                }
+                if corners.len() < 3 {
+                    continue;
+                }
+                let points: Vec<Point3<f32>> = corners.iter().map(|c| positions[c.0]).collect();
+                let normal = face_normal(&points);
+                let triangles = if corners.len() == 3 {
+                    vec![0, 1, 2]
+                } else {
+                    triangulate(&project_to_plane(&points, &[points.len()]))
+                };
+
+                let group = model.group(material_name.clone());
+                let first = group.vertices.len() as u32;
+                for &(position, uv, vertex_normal) in &corners {
+                    group.vertices.push(ModelVertex {
+                        position: positions[position],
+                        normal: vertex_normal.map_or(normal, |n| normals[n]),
+                        uv: uv.map_or([0.0, 0.0], |uv| uvs[uv]),
+                    });
+                }
+                group
+                    .triangles
+                    .extend(triangles.iter().map(|index| first + index));
+            }
+            "usemtl" => {
+                let name = words[1..].join(" ");
+                material_name = if name.is_empty() { None } else { Some(name) };
+            }
+            "mtllib" => {
+                let directory = path.parent().unwrap_or(Path::new(""));
+                for file in &words[1..] {
+                    let mtl_path = directory.join(file);
+                    if let Err(e) = load_materials(&mtl_path, &mut model.materials) {
+                        return Err(io::Error::new(
+                            e.kind(),
+                            format!(
+                                "{}:{}: could not load {}: {}",
+                                path.display(),
+                                line_number,
+                                mtl_path.display(),
+                                e
+                            ),
+                        ));
+                    }
+                }
+            }
+            // objects, groups and smoothing groups are all drawn alike
+            _ => (),
+        }
+    }
+
+    if normalize {
+        model.normalize();
+    }
+    Ok(model)
+}
+
+// Draws a model with the current transformation. Faces with a material from
+// an MTL file are drawn in its colours and the rest with the fill and
+// material.
+pub fn model(model: &Model) {
+    let fill;
+    let material;
+    let transform;
+    let normal_transform;
+    {
+        let sketch = sketch::get_sketch();
+        fill = sketch.fill.clone();
+        material = sketch.material.clone();
+        transform = get_transformation(&sketch.transformation);
+        normal_transform = get_normal_transformation(&sketch);
+    }
+    for group in &model.groups {
+        if group.triangles.is_empty() {
+            continue;
+        }
+        let model_material = group
+            .material_name
+            .as_ref()
+            .and_then(|name| model.materials.get(name));
+        let color = match model_material {
+            Some(model_material) => model_material.diffuse.clone(),
+            None => fill.clone(),
+        };
+        if color.w <= 0.0 {
+            continue;
+        }
+        let mut mesh = Mesh::new(transform, normal_transform, false);
+        for vertex in &group.vertices {
+            mesh.push_vertex_normal(&vertex.position, &vertex.normal, &vertex.uv, &color);
+        }
+        mesh.push_triangles(&group.triangles);
+
+        sketch::get_sketch().material = match model_material {
+            Some(model_material) => model_material.material.clone(),
+            None => material.clone(),
+        };
+        mesh.draw();
+    }
+    sketch::get_sketch().material = material;
+}
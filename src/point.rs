@@ -24,14 +24,21 @@
 
 use ellipse::*;
 use shape::Shape;
-use sketch::get_stroke_weight;
+use sketch;
 
 use na::Point3;
 
-use std::cmp;
-
 pub fn point(point: Point3<f32>) {
-    let diameter = get_stroke_weight() as f32;
-    let n_segments = cmp::max(diameter.log2().ceil() as u32, 4);
-    Ellipse::new(point, diameter, diameter, 1 << n_segments, true).draw();
+    let diameter;
+    let n_segments;
+    {
+        let sketch = sketch::get_sketch();
+        if sketch.stroke.w <= 0.0 || sketch.stroke_weight <= 0.0 {
+            return;
+        }
+        // sub-pixel points are drawn a pixel wide with reduced opacity by Ellipse
+        diameter = sketch.stroke_weight.max(1.0);
+        n_segments = screen_segments(&sketch, &point, 0.5 * diameter, 0.5 * diameter);
+    }
+    Ellipse::new(point, diameter, diameter, n_segments, true).draw();
 }
@@ -22,21 +22,169 @@
  * SOFTWARE.
  */
 
+use color::{get_fill, get_stroke};
+use glapp::VBO_STRIDE_N;
+use mesh::{transform_normal, Mesh};
 use shape;
 use shape::*;
 use sketch;
-use transformation::get_transformations;
+use sketch::SKETCH;
+use stroke::polyline;
+use transformation::{get_normal_transformation, get_transformation};
+use utils::*;
 
-use na::{Point3, Rotation3, Vector3};
+use na::{Point3, Vector3};
 
-use std::f32;
+use std::f32::consts::*;
 
+const N_CORNER_SEGMENTS: u32 = 16;
+
+// How the position and size arguments of rect_sized(), rect_rounded(),
+// ellipse() and arc() are interpreted
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShapeMode {
+    // position is the top-left corner and the size is the width and height
+    Corner,
+    // position is one corner and the size is the x and y of the opposite one
+    Corners,
+    // position is the centre and the size is the width and height
+    Center,
+    // position is the centre and the size is half the width and height
+    Radius,
+}
+
+pub fn rect_mode(mode: ShapeMode) {
+    SKETCH.lock().unwrap().rect_mode = mode;
+}
+
+// Returns the top-left and bottom-right corners of the bounding box described
+// by the position and size according to the mode. Negative sizes flip the box
+// rather than turning it inside out.
+pub fn mode_corners(
+    mode: ShapeMode,
+    position: Point3<f32>,
+    width: f32,
+    height: f32,
+) -> (Point3<f32>, Point3<f32>) {
+    let (x, y, z) = (position.x, position.y, position.z);
+    let (x0, y0, x1, y1) = match mode {
+        ShapeMode::Corner => (x, y, x + width, y - height),
+        ShapeMode::Corners => (x, y, width, height),
+        ShapeMode::Center => (
+            x - 0.5 * width,
+            y + 0.5 * height,
+            x + 0.5 * width,
+            y - 0.5 * height,
+        ),
+        ShapeMode::Radius => (x - width, y + height, x + width, y - height),
+    };
+    (
+        Point3::new(x0.min(x1), y0.max(y1), z),
+        Point3::new(x0.max(x1), y0.min(y1), z),
+    )
+}
+
+// Draws a rectangle between two opposite corners, regardless of rect_mode()
 pub fn rect(top_left: Point3<f32>, bottom_right: Point3<f32>) {
-    Rectangle::new(top_left, bottom_right, false, false).draw();
+    if get_fill().w > 0.0 {
+        Rectangle::new(top_left, bottom_right, false).draw();
+    }
+    if get_stroke().w > 0.0 {
+        let top_right = Point3::new(bottom_right.x, top_left.y, top_left.z);
+        let bottom_left = Point3::new(top_left.x, bottom_right.y, bottom_right.z);
+        polyline(&[top_left, top_right, bottom_right, bottom_left], true);
+    }
+}
+
+// Draws a rectangle with the position and size interpreted by rect_mode()
+pub fn rect_sized(position: Point3<f32>, width: f32, height: f32) {
+    let mode = SKETCH.lock().unwrap().rect_mode;
+    let (top_left, bottom_right) = mode_corners(mode, position, width, height);
+    rect(top_left, bottom_right);
+}
+
+// Draws a rectangle with rounded corners, with the position and size
+// interpreted by rect_mode(). radii holds either one radius for all corners or
+// one for each of the top-left, top-right, bottom-right and bottom-left
+// corners, and any other number of radii is ignored, leaving the corners
+// square. Radii are limited to half the shorter side.
+pub fn rect_rounded(position: Point3<f32>, width: f32, height: f32, radii: &[f32]) {
+    debug_assert!(
+        radii.len() == 1 || radii.len() == 4,
+        "rect_rounded() takes 1 or 4 radii, not {}",
+        radii.len()
+    );
+    let fill;
+    let stroke;
+    let mode;
+    let mut mesh;
+    {
+        let sketch = sketch::get_sketch();
+        fill = sketch.fill.clone();
+        stroke = sketch.stroke.clone();
+        mode = sketch.rect_mode;
+        mesh = Mesh::new(
+            get_transformation(&sketch.transformation),
+            get_normal_transformation(&sketch),
+            false,
+        );
+    }
+
+    let (top_left, bottom_right) = mode_corners(mode, position, width, height);
+    let (l, t, r, b) = (top_left.x, top_left.y, bottom_right.x, bottom_right.y);
+    let max_radius = 0.5 * (r - l).min(t - b);
+    let corner_radius = |i: usize| -> f32 {
+        let radius = match radii.len() {
+            1 => radii[0],
+            4 => radii[i],
+            _ => 0.0,
+        };
+        radius.max(0.0).min(max_radius)
+    };
+
+    // walk clockwise around the corners, starting from the top of the left edge
+    let corners = [
+        (Vector3::new(l, t, 0.0), Vector3::new(1.0, -1.0, 0.0), PI),
+        (Vector3::new(r, t, 0.0), Vector3::new(-1.0, -1.0, 0.0), FRAC_PI_2),
+        (Vector3::new(r, b, 0.0), Vector3::new(-1.0, 1.0, 0.0), 0.0),
+        (Vector3::new(l, b, 0.0), Vector3::new(1.0, 1.0, 0.0), -FRAC_PI_2),
+    ];
+    let mut points = Vec::with_capacity(4 * (N_CORNER_SEGMENTS as usize + 1));
+    for i in 0..corners.len() {
+        let (corner, inwards, start) = corners[i];
+        let radius = corner_radius(i);
+        let centre = Point3::new(0.0, 0.0, top_left.z) + corner + inwards * radius;
+        if radius == 0.0 {
+            points.push(centre);
+            continue;
+        }
+        for j in 0..N_CORNER_SEGMENTS + 1 {
+            let angle = start - FRAC_PI_2 * j as f32 / N_CORNER_SEGMENTS as f32;
+            points.push(centre + Vector3::new(radius * angle.cos(), radius * angle.sin(), 0.0));
+        }
+    }
+
+    if fill.w > 0.0 {
+        // rounded rectangles are convex so a fan from the centre covers them
+        let centre = Point3::new(0.5 * (l + r), 0.5 * (t + b), top_left.z);
+        let uv = |p: &Point3<f32>| [map_f32(p.x, l, r, 0.0, 1.0), map_f32(p.y, b, t, 0.0, 1.0)];
+        mesh.push_vertex(&centre, &[0.5, 0.5], &fill);
+        for p in &points {
+            mesh.push_vertex(p, &uv(p), &fill);
+        }
+        let n = points.len() as u32;
+        for i in 1..n + 1 {
+            mesh.push_triangle(0, i, i % n + 1);
+        }
+        mesh.draw();
+    }
+    if stroke.w > 0.0 {
+        polyline(&points, true);
+    }
 }
 
 pub struct Rectangle {
-    vertex_data: [f32; 9 * 4],
+    vertex_data: [f32; VBO_STRIDE_N * 4],
     index_data: [u32; 4],
     is_stroke: bool,
 }
@@ -44,73 +192,47 @@ pub struct Rectangle {
 pub fn get_rect_vertex_data(
     top_left: Point3<f32>,
     bottom_right: Point3<f32>,
-    is_line: bool,
+    is_stroke: bool,
     vertex_data: &mut [f32],
 ) {
-    let mut top_left = top_left;
-    let mut bottom_right = bottom_right;
-    let top_right;
-    let bottom_left;
+    let bottom_left = Point3::new(top_left.x, bottom_right.y, bottom_right.z);
+    let top_right = Point3::new(bottom_right.x, top_left.y, top_left.z);
     let uvs: [f32; 8] = [0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0];
 
     let sketch = sketch::get_sketch();
-    if is_line {
-        // FIXME: Only works in 2D - need z = 0 to define a plane with the two points
-        let width = sketch.stroke_weight;
-        let line = (bottom_right - top_left).normalize() * (width as f32 * 0.5).ceil();
-        let anticlockwise =
-            Rotation3::from_axis_angle(&Vector3::z_axis(), f32::consts::FRAC_PI_2) * line;
-        let clockwise =
-            Rotation3::from_axis_angle(&Vector3::z_axis(), -f32::consts::FRAC_PI_2) * line;
-
-        bottom_left = top_left + clockwise;
-        top_right = bottom_right + anticlockwise;
-        top_left = top_left + anticlockwise;
-        bottom_right = bottom_right + clockwise;
-    } else {
-        bottom_left = Point3::new(top_left.x, bottom_right.y, bottom_right.z);
-        top_right = Point3::new(bottom_right.x, top_left.y, top_left.z);
-    }
-
     let color;
-    if is_line {
+    if is_stroke {
         color = &sketch.stroke;
     } else {
         color = &sketch.fill;
     }
 
-    let mut transform = sketch.transformation;
-    {
-        let transformations = get_transformations();
-        if let Some(transformation) = transformations.last() {
-            transform *= transformation;
-        }
-    }
+    let transform = get_transformation(&sketch.transformation);
 
+    let normal = match get_normal_transformation(&sketch) {
+        Some(normal_transform) => transform_normal(&normal_transform, &Vector3::z()),
+        None => Vector3::z(),
+    };
     let points = [top_left, bottom_left, top_right, bottom_right];
     for i in 0..points.len() {
-        assign_vertex(
+        assign_vertex_normal(
             &(transform * points[i]),
+            &normal,
             &uvs[i * 2..],
             color,
-            &mut vertex_data[i * 9..],
+            &mut vertex_data[i * VBO_STRIDE_N..],
         );
     }
 }
 
 impl Rectangle {
-    pub fn new(
-        top_left: Point3<f32>,
-        bottom_right: Point3<f32>,
-        is_stroke: bool,
-        is_line: bool,
-    ) -> Rectangle {
+    pub fn new(top_left: Point3<f32>, bottom_right: Point3<f32>, is_stroke: bool) -> Rectangle {
         let mut rectangle = Rectangle {
-            vertex_data: [0.0; 9 * 4],
+            vertex_data: [0.0; VBO_STRIDE_N * 4],
             index_data: [0, 1, 2, 3],
             is_stroke,
         };
-        get_rect_vertex_data(top_left, bottom_right, is_line, &mut rectangle.vertex_data);
+        get_rect_vertex_data(top_left, bottom_right, is_stroke, &mut rectangle.vertex_data);
         rectangle
     }
 }
@@ -134,4 +256,11 @@ impl Shape for Rectangle {
     fn is_stroke(&self) -> bool {
         self.is_stroke
     }
+    fn uv_edges(&self) -> UvEdges {
+        if self.is_stroke {
+            UvEdges::None
+        } else {
+            UvEdges::Rectangle
+        }
+    }
 }
@@ -33,20 +33,263 @@ pub const DEFAULT_VERTEX_SHADER: &'static str = "#version 330 core\n\
     layout (location = 0) in vec3 position;\n\
     layout (location = 1) in vec2 uv;\n\
     layout (location = 2) in vec4 a_color;\n\
+    layout (location = 3) in vec3 normal;\n\
+    uniform mat4 projection;\n\
     out vec4 color;\n\
     out vec2 tex_coord;\n\
     void main() {\n\
-        gl_Position = vec4(position.x, position.y, position.z, 1.0);\n\
+        gl_Position = projection * vec4(position, 1.0);\n\
         color = a_color;\n\
         tex_coord = uv;\n\
     }";
 
-pub const DEFAULT_FRAGMENT_SHADER: &'static str = "#version 330 core\n\
+// GLSL for paint(), which multiplies a colour by the gradient of
+// fill_gradient() or the texture of fill_feedback() at the texture
+// coordinates, if there is one, and fades it out at the edges given by
+// uv_edges. The gradient kinds are those of
+// GradientKind in order after none, the edges are those of UvEdges in order,
+// and MAX_GRADIENT_STOPS matches gradient::MAX_GRADIENT_STOPS.
+macro_rules! paint_glsl {
+    () => {
+        "const int MAX_GRADIENT_STOPS = 8;\n\
+        const int LINEAR = 1;\n\
+        const int RADIAL = 2;\n\
+        uniform int gradient_kind;\n\
+        uniform vec4 gradient_geometry;\n\
+        uniform int gradient_stop_count;\n\
+        uniform float gradient_offset[MAX_GRADIENT_STOPS];\n\
+        uniform vec4 gradient_color[MAX_GRADIENT_STOPS];\n\
+        uniform bool has_fill_texture;\n\
+        uniform sampler2D fill_texture;\n\
+        uniform int uv_edges;\n\
+        // the fraction of the pixel inside the edges, from how far the pixel\n\
+        // centre is from them in pixels\n\
+        float edge_coverage(vec2 uv) {\n\
+            if (uv_edges == 1) {\n\
+                vec2 d = min(uv, 1.0 - uv) / max(fwidth(uv), 1e-6);\n\
+                return clamp(min(d.x, d.y) + 0.5, 0.0, 1.0);\n\
+            } else if (uv_edges == 2) {\n\
+                float r = length(2.0 * uv - 1.0);\n\
+                return clamp((1.0 - r) / max(fwidth(r), 1e-6) + 0.5, 0.0, 1.0);\n\
+            } else if (uv_edges == 3) {\n\
+                float d = min(uv.x, 1.0 - uv.x) / max(fwidth(uv.x), 1e-6);\n\
+                return clamp(d + 0.5, 0.0, 1.0);\n\
+            }\n\
+            return 1.0;\n\
+        }\n\
+        vec4 gradient_paint(vec4 color, vec2 uv) {\n\
+            if (gradient_kind == 0 || gradient_stop_count == 0) {\n\
+                return color;\n\
+            }\n\
+            vec2 origin = gradient_geometry.xy;\n\
+            float t;\n\
+            if (gradient_kind == LINEAR) {\n\
+                vec2 axis = gradient_geometry.zw - origin;\n\
+                t = dot(uv - origin, axis) / max(dot(axis, axis), 1e-12);\n\
+            } else if (gradient_kind == RADIAL) {\n\
+                t = length(uv - origin) / max(gradient_geometry.z, 1e-6);\n\
+            } else {\n\
+                vec2 offset = uv - origin;\n\
+                t = fract((atan(offset.y, offset.x) - gradient_geometry.z) / 6.28318530718);\n\
+            }\n\
+            vec4 gradient = gradient_color[0];\n\
+            for (int i = 1; i < gradient_stop_count; i++) {\n\
+                float from = gradient_offset[i - 1];\n\
+                float to = gradient_offset[i];\n\
+                if (t > from) {\n\
+                    float f = clamp((t - from) / max(to - from, 1e-6), 0.0, 1.0);\n\
+                    gradient = mix(gradient_color[i - 1], gradient_color[i], f);\n\
+                }\n\
+            }\n\
+            return color * gradient;\n\
+        }\n\
+        vec4 paint(vec4 color, vec2 uv) {\n\
+            vec4 painted = gradient_paint(color, uv);\n\
+            if (has_fill_texture) {\n\
+                painted *= texture(fill_texture, uv);\n\
+            }\n\
+            return vec4(painted.rgb, painted.a * edge_coverage(uv));\n\
+        }\n"
+    };
+}
+
+pub const DEFAULT_FRAGMENT_SHADER: &'static str = concat!(
+    "#version 330 core\n",
+    paint_glsl!(),
+    "in vec4 color;\n\
+    in vec2 tex_coord;\n\
+    out vec4 frag_color;\n\
+    void main() {\n\
+        frag_color = paint(color, tex_coord);\n\
+    }"
+);
+
+// Used instead of the default shaders for fills drawn while there are lights.
+// Positions and normals are in view space, where the eye is at the origin.
+pub const LIT_VERTEX_SHADER: &'static str = "#version 330 core\n\
+    layout (location = 0) in vec3 position;\n\
+    layout (location = 1) in vec2 uv;\n\
+    layout (location = 2) in vec4 a_color;\n\
+    layout (location = 3) in vec3 normal;\n\
+    uniform mat4 projection;\n\
+    out vec4 color;\n\
+    out vec2 tex_coord;\n\
+    out vec3 view_position;\n\
+    out vec3 view_normal;\n\
+    void main() {\n\
+        gl_Position = projection * vec4(position, 1.0);\n\
+        color = a_color;\n\
+        tex_coord = uv;\n\
+        view_position = position;\n\
+        view_normal = normal;\n\
+    }";
+
+// The light kinds are those of LightKind in order and MAX_LIGHTS matches
+// light::MAX_LIGHTS. The material is the same for each batch of shapes, with
+// the ambient colour following the vertex colour unless has_ambient is set.
+pub const LIT_FRAGMENT_SHADER: &'static str = concat!(
+    "#version 330 core\n",
+    paint_glsl!(),
+    "const int MAX_LIGHTS = 8;\n\
+    const int AMBIENT = 0;\n\
+    const int DIRECTIONAL = 1;\n\
+    const int SPOT = 3;\n\
+    uniform int light_count;\n\
+    uniform int light_kind[MAX_LIGHTS];\n\
+    uniform vec3 light_color[MAX_LIGHTS];\n\
+    uniform vec3 light_specular[MAX_LIGHTS];\n\
+    uniform vec3 light_position[MAX_LIGHTS];\n\
+    uniform vec3 light_direction[MAX_LIGHTS];\n\
+    uniform vec3 light_falloff[MAX_LIGHTS];\n\
+    uniform vec2 light_spot[MAX_LIGHTS];\n\
+    uniform bool has_ambient;\n\
+    uniform vec3 material_ambient;\n\
+    uniform vec3 material_specular;\n\
+    uniform vec3 material_emissive;\n\
+    uniform float material_shininess;\n\
     in vec4 color;\n\
     in vec2 tex_coord;\n\
+    in vec3 view_position;\n\
+    in vec3 view_normal;\n\
+    out vec4 frag_color;\n\
+    void main() {\n\
+        vec3 n = normalize(view_normal);\n\
+        // surfaces are lit on whichever side faces the eye\n\
+        if (dot(n, view_position) > 0.0) {\n\
+            n = -n;\n\
+        }\n\
+        vec3 to_eye = normalize(-view_position);\n\
+        vec3 ambient_light = vec3(0.0);\n\
+        vec3 diffuse_light = vec3(0.0);\n\
+        vec3 specular_light = vec3(0.0);\n\
+        for (int i = 0; i < light_count; i++) {\n\
+            if (light_kind[i] == AMBIENT) {\n\
+                ambient_light += light_color[i];\n\
+                continue;\n\
+            }\n\
+            vec3 to_light = -light_direction[i];\n\
+            float attenuation = 1.0;\n\
+            if (light_kind[i] != DIRECTIONAL) {\n\
+                vec3 offset = light_position[i] - view_position;\n\
+                float d = length(offset);\n\
+                to_light = offset / d;\n\
+                attenuation = 1.0 / dot(light_falloff[i], vec3(1.0, d, d * d));\n\
+                if (light_kind[i] == SPOT) {\n\
+                    float spot_cos = dot(-to_light, light_direction[i]);\n\
+                    float in_cone = step(light_spot[i].x, spot_cos);\n\
+                    attenuation *= in_cone * pow(max(spot_cos, 0.0), light_spot[i].y);\n\
+                }\n\
+            }\n\
+            diffuse_light += attenuation * max(dot(n, to_light), 0.0) * light_color[i];\n\
+            vec3 halfway = normalize(to_light + to_eye);\n\
+            float highlight = pow(max(dot(n, halfway), 0.0), material_shininess);\n\
+            specular_light += attenuation * highlight * light_specular[i];\n\
+        }\n\
+        vec4 diffuse = paint(color, tex_coord);\n\
+        vec3 ambient = has_ambient ? material_ambient : color.rgb;\n\
+        vec3 lit = material_emissive + ambient * ambient_light\n\
+            + diffuse.rgb * diffuse_light + material_specular * specular_light;\n\
+        frag_color = vec4(lit, diffuse.a);\n\
+    }"
+);
+
+// Draws a rectangle over the whole viewport, from four vertices made up from
+// their indices so that no vertex data is needed, for filter()
+pub const FILTER_VERTEX_SHADER: &'static str = "#version 330 core\n\
+    out vec2 tex_coord;\n\
+    void main() {\n\
+        vec2 corner = vec2(gl_VertexID & 1, gl_VertexID >> 1);\n\
+        tex_coord = corner;\n\
+        gl_Position = vec4(2.0 * corner - 1.0, 0.0, 1.0);\n\
+    }";
+
+// Copies a texture over the whole viewport
+pub const DISPLAY_FRAGMENT_SHADER: &'static str = "#version 330 core\n\
+    uniform sampler2D canvas;\n\
+    in vec2 tex_coord;\n\
+    out vec4 frag_color;\n\
+    void main() {\n\
+        frag_color = texture(canvas, tex_coord);\n\
+    }";
+
+// The built-in filters, with the kinds those of Filter in order. Blurs are
+// done in two passes along blur_direction.
+pub const FILTER_FRAGMENT_SHADER: &'static str = "#version 330 core\n\
+    const int BLUR = 0;\n\
+    const int THRESHOLD = 1;\n\
+    const int GRAY = 2;\n\
+    const int INVERT = 3;\n\
+    const int POSTERIZE = 4;\n\
+    const int ERODE = 5;\n\
+    const int DILATE = 6;\n\
+    const int MAX_BLUR_RADIUS = 64;\n\
+    uniform sampler2D canvas;\n\
+    uniform vec2 texel_size;\n\
+    uniform int filter_kind;\n\
+    uniform float filter_parameter;\n\
+    uniform vec2 blur_direction;\n\
+    in vec2 tex_coord;\n\
     out vec4 frag_color;\n\
+    float luminance(vec3 color) {\n\
+        return dot(color, vec3(0.3, 0.59, 0.11));\n\
+    }\n\
     void main() {\n\
-        frag_color = color;\n\
+        vec4 color = texture(canvas, tex_coord);\n\
+        if (filter_kind == BLUR) {\n\
+            int radius = min(int(ceil(filter_parameter)), MAX_BLUR_RADIUS);\n\
+            float sigma = max(0.5 * filter_parameter, 0.5);\n\
+            vec4 sum = vec4(0.0);\n\
+            float total = 0.0;\n\
+            for (int i = -radius; i <= radius; i++) {\n\
+                float weight = exp(-float(i * i) / (2.0 * sigma * sigma));\n\
+                vec2 offset = float(i) * blur_direction * texel_size;\n\
+                sum += weight * texture(canvas, tex_coord + offset);\n\
+                total += weight;\n\
+            }\n\
+            frag_color = sum / total;\n\
+        } else if (filter_kind == THRESHOLD) {\n\
+            float level = step(filter_parameter, luminance(color.rgb));\n\
+            frag_color = vec4(vec3(level), color.a);\n\
+        } else if (filter_kind == GRAY) {\n\
+            frag_color = vec4(vec3(luminance(color.rgb)), color.a);\n\
+        } else if (filter_kind == INVERT) {\n\
+            frag_color = vec4(1.0 - color.rgb, color.a);\n\
+        } else if (filter_kind == POSTERIZE) {\n\
+            float levels = max(filter_parameter, 2.0);\n\
+            vec3 posterized = min(floor(color.rgb * levels) / (levels - 1.0), 1.0);\n\
+            frag_color = vec4(posterized, color.a);\n\
+        } else {\n\
+            vec2 neighbours[4] = vec2[](\n\
+                vec2(-1.0, 0.0), vec2(1.0, 0.0), vec2(0.0, -1.0), vec2(0.0, 1.0));\n\
+            frag_color = color;\n\
+            for (int i = 0; i < 4; i++) {\n\
+                vec4 neighbour = texture(canvas, tex_coord + neighbours[i] * texel_size);\n\
+                float difference = luminance(neighbour.rgb) - luminance(frag_color.rgb);\n\
+                if (filter_kind == ERODE ? difference < 0.0 : difference > 0.0) {\n\
+                    frag_color = neighbour;\n\
+                }\n\
+            }\n\
+        }\n\
     }";
 
 pub fn compile_shader(src: &str, shader_type: GLenum) -> GLuint {
@@ -0,0 +1,72 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use glapp;
+use run_sketch;
+
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref FRAGMENT_SHADER: Mutex<String> = Mutex::new(String::new());
+}
+
+// Wraps a Shadertoy-style fragment shader, which defines
+// `void mainImage(out vec4 fragColor, in vec2 fragCoord)`, with the uniforms
+// it can use:
+// * `vec3 iResolution`, the canvas size in pixels
+// * `float iTime` and `float iTimeDelta`, the seconds since the first frame
+//   and since the previous one
+// * `int iFrame`, counting from 0
+// * `vec4 iMouse`, the mouse in xy while its button is pressed and where it
+//   was pressed in zw, negated once it is released
+// * `sampler2D iChannel0`, the previous frame
+pub fn wrap_fragment_shader(fragment_shader: &str) -> String {
+    format!(
+        "#version 330 core\n\
+         uniform vec3 iResolution;\n\
+         uniform float iTime;\n\
+         uniform float iTimeDelta;\n\
+         uniform int iFrame;\n\
+         uniform vec4 iMouse;\n\
+         uniform sampler2D iChannel0;\n\
+         out vec4 p5_frag_color;\n\
+         {}\n\
+         void main() {{\n\
+             mainImage(p5_frag_color, gl_FragCoord.xy);\n\
+         }}",
+        fragment_shader
+    )
+}
+
+fn draw() {
+    glapp::draw_shader_sketch(FRAGMENT_SHADER.lock().unwrap().clone());
+}
+
+// Runs a sketch that draws each frame with a Shadertoy-style fragment shader
+// over the whole canvas, as described by wrap_fragment_shader(). The setup
+// can set the size and smoothness.
+pub fn run_shader_sketch(setup: fn(), fragment_shader: &str, log: bool) {
+    *FRAGMENT_SHADER.lock().unwrap() = wrap_fragment_shader(fragment_shader);
+    run_sketch(setup, draw, log);
+}
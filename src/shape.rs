@@ -25,7 +25,22 @@
 use color::Color;
 use glapp::*;
 
-use na::Point3;
+use na::{Point3, Vector3};
+
+// Where a shape's edges are in its texture coordinates, for smoothing them in
+// the fragment shader when smooth() cannot be done by multisampling. Triangles,
+// quads, arcs, rounded rectangles and custom shapes have None as their texture
+// coordinates span their bounding box, so their fills keep hard edges.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UvEdges {
+    None,
+    // where u or v is 0 or 1
+    Rectangle,
+    // where (u, v) is half a unit from (0.5, 0.5)
+    Ellipse,
+    // where u is 0 or 1, as u runs across a stroke from one side to the other
+    Stroke,
+}
 
 pub trait Shape {
     fn vertex_data(&self) -> &[f32];
@@ -34,9 +49,21 @@ pub trait Shape {
     fn fragment_shader(&self) -> Option<String>;
     fn draw(&self);
     fn is_stroke(&self) -> bool;
+    fn uv_edges(&self) -> UvEdges;
 }
 
+// Flat shapes face the viewer, along +z
 pub fn assign_vertex(p: &Point3<f32>, uv: &[f32], c: &Color, vd: &mut [f32]) {
+    assign_vertex_normal(p, &Vector3::z(), uv, c, vd);
+}
+
+pub fn assign_vertex_normal(
+    p: &Point3<f32>,
+    n: &Vector3<f32>,
+    uv: &[f32],
+    c: &Color,
+    vd: &mut [f32],
+) {
     vd[0] = p.x;
     vd[1] = p.y;
     vd[2] = p.z;
@@ -46,13 +73,20 @@ pub fn assign_vertex(p: &Point3<f32>, uv: &[f32], c: &Color, vd: &mut [f32]) {
     vd[6] = c.y;
     vd[7] = c.z;
     vd[8] = c.w;
+    vd[9] = n.x;
+    vd[10] = n.y;
+    vd[11] = n.z;
 }
 
 pub fn draw(shape: &Shape) {
     let vertex_data = shape.vertex_data();
     let index_data = shape.index_data();
-    let n_triangles = index_data.len() - 2;
-    append_data(vertex_data, index_data);
+    append_data(vertex_data, index_data, shape.is_stroke());
     let shader_program = get_shader_program(shape.vertex_shader(), shape.fragment_shader());
-    append_shape(shader_program, n_triangles as u32);
+    append_shape(
+        shader_program,
+        vertex_data,
+        shape.is_stroke(),
+        shape.uv_edges(),
+    );
 }
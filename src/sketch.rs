@@ -22,25 +22,78 @@
  * SOFTWARE.
  */
 
+use blend::BlendMode;
+use camera::{default_projection, default_view};
 use color::*;
 use glapp::{DEFAULT_HEIGHT, DEFAULT_WIDTH};
+use gradient::Gradient;
+use light::Light;
+use material::Material;
+use rectangle::ShapeMode;
+use stroke::{StrokeCap, StrokeJoin};
+use tessellation::WindingRule;
 
-use na::Transform3;
+use na::{Transform3, Vector3, Vector4};
 
-use std::sync::{Mutex, MutexGuard};
+use std::sync::{Arc, Mutex, MutexGuard};
 
 lazy_static! {
     pub static ref SKETCH: Mutex<Sketch> = Mutex::new(Sketch::new());
 }
 
+// How a sketch is drawn, chosen with size_with_renderer() as with the renderer
+// argument of Processing's size()
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Renderer {
+    // shapes are drawn in order, each over those drawn before, the default
+    P2D,
+    // shapes are hidden behind nearer ones drawn before them
+    P3D,
+}
+
 pub struct Sketch {
     pub width: u32,
     pub height: u32,
     pub background: Color,
     pub fill: Color,
+    pub fill_gradient: Option<Gradient>,
+    // the id of the feedback buffer that fills are textured with, if any
+    pub fill_texture: Option<u32>,
     pub stroke: Color,
-    pub stroke_weight: u32,
+    pub stroke_weight: f32,
+    pub stroke_cap: StrokeCap,
+    pub stroke_join: StrokeJoin,
+    pub miter_limit: f32,
+    pub rect_mode: ShapeMode,
+    pub ellipse_mode: ShapeMode,
+    pub ellipse_detail: Option<u32>,
+    pub winding_rule: WindingRule,
+    pub bezier_detail: u32,
+    pub curve_detail: u32,
+    pub curve_tightness: f32,
+    pub sphere_detail: (u32, u32),
+    pub cylinder_detail: (u32, u32),
+    pub torus_detail: (u32, u32),
+    pub blend_mode: BlendMode,
+    // samples per pixel for anti-aliasing, or 0 for none
+    pub smooth: u32,
+    pub renderer: Renderer,
+    // whether hint() has turned depth testing on or off, otherwise it is on
+    // for the P3D renderer
+    pub depth_test_hint: Option<bool>,
+    pub depth_mask: bool,
+    pub depth_sort: bool,
+    // shared with the shapes drawn with them until the lights change
+    pub lights: Arc<Vec<Light>>,
+    pub light_falloff: Vector3<f32>,
+    pub light_specular: Vector3<f32>,
+    pub material: Material,
+    pub normal_material: bool,
+    // the camera, from world space into view space where the eye is at the
+    // origin looking along -z
     pub transformation: Transform3<f32>,
+    // from view space into clip space, applied by the vertex shader
+    pub projection: Transform3<f32>,
 }
 
 impl Sketch {
@@ -48,23 +101,60 @@ impl Sketch {
         Sketch {
             width: DEFAULT_WIDTH,
             height: DEFAULT_HEIGHT,
-            background: 1.0.into(),
-            fill: (0.0, 1.0, 0.0).into(),
-            stroke: 0.0.into(),
-            stroke_weight: 1,
-            transformation: Transform3::identity(),
+            background: Vector4::new(1.0, 1.0, 1.0, 1.0).into(),
+            fill: Vector4::new(0.0, 1.0, 0.0, 1.0).into(),
+            fill_gradient: None,
+            fill_texture: None,
+            stroke: Vector4::new(0.0, 0.0, 0.0, 1.0).into(),
+            stroke_weight: 1.0,
+            stroke_cap: StrokeCap::Round,
+            stroke_join: StrokeJoin::Miter,
+            miter_limit: 10.0,
+            rect_mode: ShapeMode::Corner,
+            ellipse_mode: ShapeMode::Center,
+            ellipse_detail: None,
+            winding_rule: WindingRule::NonZero,
+            bezier_detail: 20,
+            curve_detail: 20,
+            curve_tightness: 0.0,
+            sphere_detail: (30, 30),
+            cylinder_detail: (24, 1),
+            torus_detail: (24, 16),
+            blend_mode: BlendMode::Blend,
+            smooth: 2,
+            renderer: Renderer::P2D,
+            depth_test_hint: None,
+            depth_mask: true,
+            depth_sort: false,
+            lights: Arc::new(Vec::new()),
+            light_falloff: Vector3::new(1.0, 0.0, 0.0),
+            light_specular: Vector3::zeros(),
+            material: Material::new(),
+            normal_material: false,
+            transformation: default_view(DEFAULT_HEIGHT),
+            projection: default_projection(DEFAULT_WIDTH, DEFAULT_HEIGHT),
         }
     }
+
+    pub fn depth_test(&self) -> bool {
+        self.depth_test_hint.unwrap_or(self.renderer == Renderer::P3D)
+    }
+
+    // Whether shapes drawn now need their normals, to be lit or coloured by
+    // normal_material()
+    pub fn uses_normals(&self) -> bool {
+        !self.lights.is_empty() || self.normal_material
+    }
 }
 
 pub fn get_sketch<'a>() -> MutexGuard<'a, Sketch> {
     SKETCH.lock().unwrap()
 }
 
-pub fn stroke_weight(w: u32) {
+pub fn stroke_weight(w: f32) {
     SKETCH.lock().unwrap().stroke_weight = w;
 }
 
-pub fn get_stroke_weight() -> u32 {
+pub fn get_stroke_weight() -> f32 {
     SKETCH.lock().unwrap().stroke_weight
 }
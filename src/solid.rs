@@ -0,0 +1,315 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use color::Color;
+use mesh::Mesh;
+use shape::Shape;
+use sketch;
+use sketch::SKETCH;
+use stroke::polyline;
+use transformation::{get_normal_transformation, get_transformation};
+
+use na::{Point3, Vector3};
+
+use std::f32::consts::*;
+use std::ops::Range;
+
+// The number of segments around and along spheres
+pub fn sphere_detail(u: u32, v: u32) {
+    SKETCH.lock().unwrap().sphere_detail = (u.max(3), v.max(2));
+}
+
+// The number of segments around and along cylinders and cones
+pub fn cylinder_detail(u: u32, v: u32) {
+    SKETCH.lock().unwrap().cylinder_detail = (u.max(3), v.max(1));
+}
+
+// The number of segments around the ring and around the tube of tori
+pub fn torus_detail(u: u32, v: u32) {
+    SKETCH.lock().unwrap().torus_detail = (u.max(3), v.max(3));
+}
+
+// A grid of (cols + 1) x (rows + 1) vertices over a parametric surface, where u
+// runs across the columns and v along the rows, both from 0 to 1
+struct Surface {
+    cols: usize,
+    rows: usize,
+    points: Vec<Point3<f32>>,
+    normals: Vec<Vector3<f32>>,
+}
+
+impl Surface {
+    fn new<F>(cols: u32, rows: u32, f: F) -> Surface
+    where
+        F: Fn(f32, f32) -> (Point3<f32>, Vector3<f32>),
+    {
+        let (cols, rows) = (cols as usize, rows as usize);
+        let mut surface = Surface {
+            cols,
+            rows,
+            points: Vec::with_capacity((cols + 1) * (rows + 1)),
+            normals: Vec::with_capacity((cols + 1) * (rows + 1)),
+        };
+        for j in 0..rows + 1 {
+            for i in 0..cols + 1 {
+                let (p, n) = f(i as f32 / cols as f32, j as f32 / rows as f32);
+                surface.points.push(p);
+                surface.normals.push(n);
+            }
+        }
+        surface
+    }
+
+    fn index(&self, i: usize, j: usize) -> usize {
+        j * (self.cols + 1) + i
+    }
+
+    fn fill(&self, color: &Color, mesh: &mut Mesh) {
+        let first = mesh.n_vertices() as u32;
+        for j in 0..self.rows + 1 {
+            for i in 0..self.cols + 1 {
+                let index = self.index(i, j);
+                mesh.push_vertex_normal(
+                    &self.points[index],
+                    &self.normals[index],
+                    &[i as f32 / self.cols as f32, j as f32 / self.rows as f32],
+                    color,
+                );
+            }
+        }
+        for j in 0..self.rows {
+            let mut strip = Vec::with_capacity(2 * (self.cols + 1));
+            for i in 0..self.cols + 1 {
+                strip.push(first + self.index(i, j + 1) as u32);
+                strip.push(first + self.index(i, j) as u32);
+            }
+            mesh.push_strip(&strip);
+        }
+    }
+
+    // Strokes the grid lines of the given rows and of every column. Rows that
+    // wrap around in u and columns that wrap around in v are closed.
+    fn stroke(&self, rows: Range<usize>, wrap_u: bool, wrap_v: bool) {
+        for j in rows {
+            let start = self.index(0, j);
+            polyline(&self.points[start..start + self.cols + 1], wrap_u);
+        }
+        let cols = if wrap_u { self.cols } else { self.cols + 1 };
+        for i in 0..cols {
+            let column: Vec<Point3<f32>> = (0..self.rows + 1)
+                .map(|j| self.points[self.index(i, j)])
+                .collect();
+            polyline(&column, wrap_v);
+        }
+    }
+}
+
+// The point on a circle of the given radius around the y-axis at the angle u
+// of a full turn, starting from +z
+fn around_y(u: f32, radius: f32, y: f32) -> Point3<f32> {
+    let (sin, cos) = (2.0 * PI * u).sin_cos();
+    Point3::new(radius * sin, y, radius * cos)
+}
+
+// Fills a disc around the y-axis facing up or down, as the cap of a cylinder
+// or cone
+fn fill_cap(radius: f32, y: f32, n_segments: u32, color: &Color, mesh: &mut Mesh) {
+    let normal = Vector3::new(0.0, y.signum(), 0.0);
+    let center = mesh.push_vertex_normal(&Point3::new(0.0, y, 0.0), &normal, &[0.5, 0.5], color);
+    for i in 0..n_segments + 1 {
+        let p = around_y(i as f32 / n_segments as f32, radius, y);
+        let uv = [0.5 * (p.x / radius + 1.0), 0.5 * (p.z / radius + 1.0)];
+        mesh.push_vertex_normal(&p, &normal, &uv, color);
+    }
+    for i in 1..n_segments + 1 {
+        mesh.push_triangle(center, center + i, center + i + 1);
+    }
+}
+
+// Returns the fill and stroke colours, the mesh to fill into and the detail
+// chosen for the kind of solid
+fn begin_solid<F>(detail: F) -> (Color, Color, Mesh, (u32, u32))
+where
+    F: Fn(&sketch::Sketch) -> (u32, u32),
+{
+    let sketch = sketch::get_sketch();
+    (
+        sketch.fill.clone(),
+        sketch.stroke.clone(),
+        Mesh::new(
+            get_transformation(&sketch.transformation),
+            get_normal_transformation(&sketch),
+            false,
+        ),
+        detail(&sketch),
+    )
+}
+
+// Draws a box centred on the origin. Named box_ as box is a reserved word.
+pub fn box_(width: f32, height: f32, depth: f32) {
+    let (fill, stroke, mut mesh, _) = begin_solid(|_| (1, 1));
+    let half = Vector3::new(0.5 * width, 0.5 * height, 0.5 * depth);
+    let corner = |x: f32, y: f32, z: f32| Point3::new(x * half.x, y * half.y, z * half.z);
+
+    if fill.w > 0.0 {
+        // each face is the normal and the directions of increasing u and v
+        let faces = [
+            (Vector3::x(), -Vector3::z(), Vector3::y()),
+            (-Vector3::x(), Vector3::z(), Vector3::y()),
+            (Vector3::y(), Vector3::x(), -Vector3::z()),
+            (-Vector3::y(), Vector3::x(), Vector3::z()),
+            (Vector3::z(), Vector3::x(), Vector3::y()),
+            (-Vector3::z(), -Vector3::x(), Vector3::y()),
+        ];
+        for &(n, u, v) in faces.iter() {
+            let mut strip = [0; 4];
+            for (k, &(su, sv)) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+                .iter()
+                .enumerate()
+            {
+                let d = n + u * su + v * sv;
+                strip[k] = mesh.push_vertex_normal(
+                    &corner(d.x, d.y, d.z),
+                    &n,
+                    &[0.5 * (su + 1.0), 0.5 * (sv + 1.0)],
+                    &fill,
+                );
+            }
+            mesh.push_strip(&strip);
+        }
+        mesh.draw();
+    }
+
+    if stroke.w > 0.0 {
+        let ring = |y: f32| {
+            [
+                corner(-1.0, y, 1.0),
+                corner(1.0, y, 1.0),
+                corner(1.0, y, -1.0),
+                corner(-1.0, y, -1.0),
+            ]
+        };
+        let (bottom, top) = (ring(-1.0), ring(1.0));
+        polyline(&bottom, true);
+        polyline(&top, true);
+        for k in 0..4 {
+            polyline(&[bottom[k], top[k]], false);
+        }
+    }
+}
+
+// Draws a sphere centred on the origin with sphere_detail() segments
+pub fn sphere(radius: f32) {
+    let (fill, stroke, mut mesh, (cols, rows)) = begin_solid(|sketch| sketch.sphere_detail);
+    let surface = Surface::new(cols, rows, |u, v| {
+        let (sin, cos) = (PI * (v - 0.5)).sin_cos();
+        let n = around_y(u, cos, sin) - Point3::origin();
+        (Point3::origin() + n * radius, n)
+    });
+    if fill.w > 0.0 {
+        surface.fill(&fill, &mut mesh);
+        mesh.draw();
+    }
+    if stroke.w > 0.0 {
+        // the first and last rows are the poles
+        surface.stroke(1..rows as usize, true, false);
+    }
+}
+
+// Draws a rectangle in the xy-plane centred on the origin, facing +z
+pub fn plane(width: f32, height: f32) {
+    let (fill, stroke, mut mesh, _) = begin_solid(|_| (1, 1));
+    let surface = Surface::new(1, 1, |u, v| {
+        (
+            Point3::new((u - 0.5) * width, (v - 0.5) * height, 0.0),
+            Vector3::z(),
+        )
+    });
+    if fill.w > 0.0 {
+        surface.fill(&fill, &mut mesh);
+        mesh.draw();
+    }
+    if stroke.w > 0.0 {
+        surface.stroke(0..2, false, false);
+    }
+}
+
+// Draws a capped cylinder around the y-axis centred on the origin with
+// cylinder_detail() segments
+pub fn cylinder(radius: f32, height: f32) {
+    let (fill, stroke, mut mesh, (cols, rows)) = begin_solid(|sketch| sketch.cylinder_detail);
+    let surface = Surface::new(cols, rows, |u, v| {
+        let n = around_y(u, 1.0, 0.0) - Point3::origin();
+        (around_y(u, radius, (v - 0.5) * height), n)
+    });
+    if fill.w > 0.0 {
+        surface.fill(&fill, &mut mesh);
+        fill_cap(radius, -0.5 * height, cols, &fill, &mut mesh);
+        fill_cap(radius, 0.5 * height, cols, &fill, &mut mesh);
+        mesh.draw();
+    }
+    if stroke.w > 0.0 {
+        surface.stroke(0..rows as usize + 1, true, false);
+    }
+}
+
+// Draws a cone around the y-axis centred on the origin, with its base at the
+// bottom and its tip at the top, with cylinder_detail() segments
+pub fn cone(radius: f32, height: f32) {
+    let (fill, stroke, mut mesh, (cols, rows)) = begin_solid(|sketch| sketch.cylinder_detail);
+    let surface = Surface::new(cols, rows, |u, v| {
+        let p = around_y(u, height, radius);
+        let n = Vector3::new(p.x, p.y, p.z).normalize();
+        (around_y(u, (1.0 - v) * radius, (v - 0.5) * height), n)
+    });
+    if fill.w > 0.0 {
+        surface.fill(&fill, &mut mesh);
+        fill_cap(radius, -0.5 * height, cols, &fill, &mut mesh);
+        mesh.draw();
+    }
+    if stroke.w > 0.0 {
+        // the last row is the tip
+        surface.stroke(0..rows as usize, true, false);
+    }
+}
+
+// Draws a torus around the y-axis centred on the origin, where the radius is
+// to the centre of the tube, with torus_detail() segments
+pub fn torus(radius: f32, tube_radius: f32) {
+    let (fill, stroke, mut mesh, (cols, rows)) = begin_solid(|sketch| sketch.torus_detail);
+    let surface = Surface::new(cols, rows, |u, v| {
+        let (sin, cos) = (2.0 * PI * v).sin_cos();
+        let n = around_y(u, cos, sin) - Point3::origin();
+        let center = around_y(u, radius, 0.0);
+        (center + n * tube_radius, n)
+    });
+    if fill.w > 0.0 {
+        surface.fill(&fill, &mut mesh);
+        mesh.draw();
+    }
+    if stroke.w > 0.0 {
+        // the last row is the same as the first
+        surface.stroke(0..rows as usize, true, true);
+    }
+}
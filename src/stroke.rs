@@ -0,0 +1,433 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use color::Color;
+use mesh::Mesh;
+use shape::Shape;
+use sketch;
+use sketch::{Sketch, SKETCH};
+use transformation::get_screen_transformations;
+
+use na::{Matrix4, Point3, Transform3, Vector2};
+
+use std::cmp;
+use std::f32;
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StrokeCap {
+    Round,
+    // ends flat at the end points
+    Square,
+    // ends flat half the stroke weight beyond the end points
+    Project,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StrokeJoin {
+    Miter,
+    Bevel,
+    Round,
+}
+
+pub fn stroke_cap(cap: StrokeCap) {
+    SKETCH.lock().unwrap().stroke_cap = cap;
+}
+
+pub fn stroke_join(join: StrokeJoin) {
+    SKETCH.lock().unwrap().stroke_join = join;
+}
+
+// Miter joins longer than limit times the stroke weight are beveled instead
+pub fn miter_limit(limit: f32) {
+    SKETCH.lock().unwrap().miter_limit = limit.max(1.0);
+}
+
+// Strokes thinner than a pixel cannot be rasterized at their true width so
+// they are drawn a pixel wide with the fraction of the pixel they would cover
+// applied to their opacity instead
+pub fn with_coverage(weight: f32, color: &Color) -> (f32, Color) {
+    let mut color = color.clone();
+    if weight < 1.0 {
+        color.w *= weight.max(0.0);
+        return (1.0, color);
+    }
+    (weight, color)
+}
+
+// Strokes are pulled this fraction of the way towards the eye so that they are
+// drawn over the fills they outline when depth testing. Scaling view space
+// about the eye keeps their position in a perspective projection. Without
+// depth testing they are left where they are.
+const STROKE_DEPTH_OFFSET: f32 = 1e-4;
+
+pub fn stroke_depth_offset(sketch: &Sketch) -> Transform3<f32> {
+    if sketch.depth_test() {
+        Transform3::from_matrix_unchecked(Matrix4::new_scaling(1.0 - STROKE_DEPTH_OFFSET))
+    } else {
+        Transform3::identity()
+    }
+}
+
+pub struct StrokeStyle {
+    pub weight: f32,
+    pub cap: StrokeCap,
+    pub join: StrokeJoin,
+    pub miter_limit: f32,
+}
+
+// Strokes the line through the points with the current stroke style, closing
+// it back to the first point if closed. The points are transformed to screen
+// space before being widened so that strokes face the viewer and keep their
+// width in pixels whatever the transformation.
+pub fn polyline(points: &[Point3<f32>], closed: bool) {
+    let style;
+    let color;
+    let screen_points: Vec<Point3<f32>>;
+    let mut mesh;
+    {
+        let sketch = sketch::get_sketch();
+        if sketch.stroke.w <= 0.0 || sketch.stroke_weight <= 0.0 {
+            return;
+        }
+        let (weight, coverage_color) = with_coverage(sketch.stroke_weight, &sketch.stroke);
+        style = StrokeStyle {
+            weight,
+            cap: sketch.stroke_cap,
+            join: sketch.stroke_join,
+            miter_limit: sketch.miter_limit,
+        };
+        color = coverage_color;
+        let (to_screen, from_screen) = get_screen_transformations(&sketch);
+        let mut transformed: Vec<Point3<f32>> = points.iter().map(|p| to_screen * *p).collect();
+        if weight.fract() == 0.0 && weight as u32 % 2 == 1 {
+            snap_to_pixel_centres(&mut transformed, closed, sketch.width, sketch.height);
+        }
+        screen_points = transformed;
+        mesh = Mesh::new(stroke_depth_offset(&sketch) * from_screen, None, true);
+    }
+    stroke_polyline(&screen_points, closed, &style, &color, &mut mesh);
+    mesh.draw();
+}
+
+// Moves the points of horizontal and vertical segments, in screen space, onto
+// the centres of the pixels they fall in, so that strokes an odd number of
+// pixels wide cover whole pixels rather than straddling two rows or columns
+fn snap_to_pixel_centres(points: &mut [Point3<f32>], closed: bool, width: u32, height: u32) {
+    let n = points.len();
+    let (half_width, half_height) = (0.5 * width as f32, 0.5 * height as f32);
+    let snap = |v: f32, half_size: f32| (v + half_size).floor() + 0.5 - half_size;
+    let original = points.to_vec();
+    for i in 0..n {
+        let mut neighbours = Vec::with_capacity(2);
+        if i > 0 || closed {
+            neighbours.push(original[(i + n - 1) % n]);
+        }
+        if i + 1 < n || closed {
+            neighbours.push(original[(i + 1) % n]);
+        }
+        let p = original[i];
+        if neighbours.iter().any(|q| (q.y - p.y).abs() < 1e-3 && q.x != p.x) {
+            points[i].y = snap(p.y, half_height);
+        }
+        if neighbours.iter().any(|q| (q.x - p.x).abs() < 1e-3 && q.y != p.y) {
+            points[i].x = snap(p.x, half_width);
+        }
+    }
+}
+
+fn perpendicular(d: &Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(-d.y, d.x)
+}
+
+fn rotate(v: &Vector2<f32>, angle: f32) -> Vector2<f32> {
+    let (sin, cos) = angle.sin_cos();
+    Vector2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+// The number of segments to approximate an arc of the given angle, using the
+// same resolution as point() for a circle of the same radius
+fn arc_segments(radius: f32, angle: f32) -> u32 {
+    let full = 1 << cmp::max((2.0 * radius).log2().ceil() as i32, 4);
+    cmp::max((full as f32 * angle.abs() / (2.0 * PI)).ceil() as u32, 1)
+}
+
+struct StrokeBuilder<'a> {
+    mesh: &'a mut Mesh,
+    color: &'a Color,
+    half_width: f32,
+}
+
+impl<'a> StrokeBuilder<'a> {
+    // Adds a vertex offset from an anchor point. The u texture coordinate runs
+    // across the stroke from 0 at the left edge to 1 at the right edge
+    // relative to the direction of travel given by normal.
+    fn vertex(&mut self, anchor: &Point3<f32>, offset: &Vector2<f32>, normal: &Vector2<f32>) -> u32 {
+        let p = Point3::new(anchor.x + offset.x, anchor.y + offset.y, anchor.z);
+        let u = 0.5 - 0.5 * offset.dot(normal) / self.half_width;
+        self.mesh.push_vertex(&p, &[u, 0.5], self.color)
+    }
+
+    // Fans triangles around pivot through the arc of radius half_width centred
+    // on anchor, from offset rotated anticlockwise by sweep. The arc end points
+    // are the existing vertices first and last.
+    fn arc(
+        &mut self,
+        pivot: u32,
+        anchor: &Point3<f32>,
+        offset: &Vector2<f32>,
+        sweep: f32,
+        normal: &Vector2<f32>,
+        first: u32,
+        last: u32,
+    ) {
+        let n_segments = arc_segments(self.half_width, sweep);
+        let mut previous = first;
+        for i in 1..n_segments {
+            let rotated = rotate(offset, sweep * i as f32 / n_segments as f32);
+            let current = self.vertex(anchor, &rotated, normal);
+            self.mesh.push_triangle(pivot, previous, current);
+            previous = current;
+        }
+        self.mesh.push_triangle(pivot, previous, last);
+    }
+
+    // Joins the segment arriving along d0 to the one leaving along d1 at p,
+    // returning the (left, right) vertices that end the first segment and
+    // those that start the second.
+    fn join(
+        &mut self,
+        p: &Point3<f32>,
+        d0: &Vector2<f32>,
+        d1: &Vector2<f32>,
+        max_depth: f32,
+        style: &StrokeStyle,
+    ) -> ((u32, u32), (u32, u32)) {
+        let h = self.half_width;
+        let n0 = perpendicular(d0);
+        let n1 = perpendicular(d1);
+        let cross = d0.x * d1.y - d0.y * d1.x;
+        let dot = d0.dot(d1);
+
+        if cross.abs() < 1e-6 && dot > 0.0 {
+            let left = self.vertex(p, &(n0 * h), &n0);
+            let right = self.vertex(p, &(-n0 * h), &n0);
+            return ((left, right), (left, right));
+        }
+
+        // the side of the line, +1 for left and -1 for right, on the inside
+        // of the turn
+        let side = if cross >= 0.0 { 1.0 } else { -1.0 };
+        let outer0 = -n0 * (h * side);
+        let outer1 = -n1 * (h * side);
+        let reversal = dot <= -1.0 + 1e-6;
+        let sweep = if reversal {
+            PI * side
+        } else {
+            cross.atan2(dot)
+        };
+
+        // the offset lines on the inside of the turn meet at p + miter * h,
+        // how far back along each segment from p is given by depth
+        let miter = if reversal {
+            Vector2::new(0.0, 0.0)
+        } else {
+            (n0 + n1) / (1.0 + dot)
+        };
+        let depth = if reversal {
+            f32::INFINITY
+        } else {
+            h * cross.abs() / (1.0 + dot)
+        };
+
+        let pivot;
+        let outer_start = self.vertex(p, &outer0, &n0);
+        let outer_end = self.vertex(p, &outer1, &n1);
+        let ends;
+        if depth <= max_depth {
+            pivot = self.vertex(p, &(miter * (h * side)), &n0);
+            if side > 0.0 {
+                ends = ((pivot, outer_start), (pivot, outer_end));
+            } else {
+                ends = ((outer_start, pivot), (outer_end, pivot));
+            }
+        } else {
+            // the segments are too short for the inside of the turn to meet
+            // within them so they overlap slightly around the centre instead
+            pivot = self.vertex(p, &Vector2::new(0.0, 0.0), &n0);
+            let inner_start = self.vertex(p, &(n0 * (h * side)), &n0);
+            let inner_end = self.vertex(p, &(n1 * (h * side)), &n1);
+            if side > 0.0 {
+                ends = ((inner_start, outer_start), (inner_end, outer_end));
+            } else {
+                ends = ((outer_start, inner_start), (outer_end, inner_end));
+            }
+        }
+
+        let join = match style.join {
+            StrokeJoin::Miter if reversal || miter.norm() > style.miter_limit => {
+                StrokeJoin::Bevel
+            }
+            join => join,
+        };
+        match join {
+            StrokeJoin::Round => {
+                self.arc(pivot, p, &outer0, sweep, &n0, outer_start, outer_end);
+            }
+            StrokeJoin::Bevel => {
+                self.mesh.push_triangle(pivot, outer_start, outer_end);
+            }
+            StrokeJoin::Miter => {
+                let tip = self.vertex(p, &(-miter * (h * side)), &n0);
+                self.mesh.push_strip(&[pivot, outer_start, outer_end, tip]);
+            }
+        }
+
+        ends
+    }
+
+    // Adds the cap at p where the stroke leaves along d, or arrives along d
+    // if at_end, returning the (left, right) vertices at the end of the stroke
+    fn cap(&mut self, p: &Point3<f32>, d: &Vector2<f32>, at_end: bool, cap: StrokeCap) -> (u32, u32) {
+        let h = self.half_width;
+        let n = perpendicular(d);
+        let extension = match cap {
+            StrokeCap::Project if at_end => *d * h,
+            StrokeCap::Project => -*d * h,
+            _ => Vector2::new(0.0, 0.0),
+        };
+        let left = self.vertex(p, &(n * h + extension), &n);
+        let right = self.vertex(p, &(-n * h + extension), &n);
+        if cap == StrokeCap::Round {
+            let pivot = self.vertex(p, &Vector2::new(0.0, 0.0), &n);
+            if at_end {
+                self.arc(pivot, p, &(-n * h), PI, &n, right, left);
+            } else {
+                self.arc(pivot, p, &(n * h), PI, &n, left, right);
+            }
+        }
+        (left, right)
+    }
+
+    // A dot for a zero-length stroke, which only has a shape with round and
+    // projecting caps
+    fn dot(&mut self, p: &Point3<f32>, cap: StrokeCap) {
+        let h = self.half_width;
+        let n = Vector2::new(0.0, 1.0);
+        match cap {
+            StrokeCap::Round => {
+                let pivot = self.vertex(p, &Vector2::new(0.0, 0.0), &n);
+                let start = self.vertex(p, &(n * h), &n);
+                self.arc(pivot, p, &(n * h), 2.0 * PI, &n, start, start);
+            }
+            StrokeCap::Project => {
+                let corners: Vec<u32> = [(-1.0, 1.0), (-1.0, -1.0), (1.0, 1.0), (1.0, -1.0)]
+                    .iter()
+                    .map(|&(x, y)| self.vertex(p, &Vector2::new(x * h, y * h), &n))
+                    .collect();
+                self.mesh.push_strip(&corners);
+            }
+            StrokeCap::Square => (),
+        }
+    }
+}
+
+// Tessellates a stroke along the points into mesh as a single mesh where no
+// triangles overlap, except at corners between segments shorter than the
+// stroke is wide, so that translucent strokes are evenly colored. The stroke
+// is widened in the xy plane.
+pub fn stroke_polyline(
+    points: &[Point3<f32>],
+    closed: bool,
+    style: &StrokeStyle,
+    color: &Color,
+    mesh: &mut Mesh,
+) {
+    let mut points: Vec<Point3<f32>> = points.to_vec();
+    points.dedup_by(|a, b| (a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6);
+    if closed && points.len() > 2 {
+        let (first, last) = (points[0], points[points.len() - 1]);
+        if (first.x - last.x).abs() < 1e-6 && (first.y - last.y).abs() < 1e-6 {
+            points.pop();
+        }
+    }
+    let closed = closed && points.len() > 2;
+
+    let mut builder = StrokeBuilder {
+        mesh,
+        color,
+        half_width: 0.5 * style.weight,
+    };
+    if points.is_empty() || builder.half_width <= 0.0 {
+        return;
+    }
+    if points.len() == 1 {
+        builder.dot(&points[0], style.cap);
+        return;
+    }
+
+    let n = points.len();
+    let n_segments = if closed { n } else { n - 1 };
+    let mut directions = Vec::with_capacity(n_segments);
+    let mut lengths = Vec::with_capacity(n_segments);
+    for i in 0..n_segments {
+        let (a, b) = (&points[i], &points[(i + 1) % n]);
+        let d = Vector2::new(b.x - a.x, b.y - a.y);
+        lengths.push(d.norm());
+        directions.push(d / d.norm());
+    }
+
+    let mut starts = vec![(0, 0); n_segments];
+    let mut ends = vec![(0, 0); n_segments];
+    if closed {
+        for i in 0..n {
+            let previous = (i + n - 1) % n;
+            let max_depth = lengths[previous].min(lengths[i]);
+            let (end, start) = builder.join(
+                &points[i],
+                &directions[previous],
+                &directions[i],
+                max_depth,
+                style,
+            );
+            ends[previous] = end;
+            starts[i] = start;
+        }
+    } else {
+        starts[0] = builder.cap(&points[0], &directions[0], false, style.cap);
+        for i in 1..n - 1 {
+            let max_depth = lengths[i - 1].min(lengths[i]);
+            let (end, start) =
+                builder.join(&points[i], &directions[i - 1], &directions[i], max_depth, style);
+            ends[i - 1] = end;
+            starts[i] = start;
+        }
+        ends[n - 2] = builder.cap(&points[n - 1], &directions[n - 2], true, style.cap);
+    }
+
+    for i in 0..n_segments {
+        let (start, end) = (starts[i], ends[i]);
+        builder.mesh.push_strip(&[start.0, start.1, end.0, end.1]);
+    }
+}
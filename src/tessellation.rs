@@ -0,0 +1,319 @@
+/*
+ * MIT License
+ *
+ * Copyright (c) 2017 Robert Swain <robert.swain@gmail.com
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use na::{Point2, Point3};
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindingRule {
+    EvenOdd,
+    NonZero,
+}
+
+impl WindingRule {
+    fn is_inside(&self, winding: i32) -> bool {
+        match *self {
+            WindingRule::EvenOdd => winding % 2 != 0,
+            WindingRule::NonZero => winding != 0,
+        }
+    }
+}
+
+// Projects planar 3D points onto the axis-aligned plane in which the polygon
+// has the largest area, using Newell's method to find the normal of each
+// contour. contour_ends holds the exclusive end index of each contour.
+pub fn project_to_plane(points: &[Point3<f32>], contour_ends: &[usize]) -> Vec<Point2<f32>> {
+    let mut normal = [0.0f32; 3];
+    let mut start = 0;
+    for &end in contour_ends {
+        let contour = &points[start..end];
+        let mut contour_normal = [0.0f32; 3];
+        for i in 0..contour.len() {
+            let p = &contour[i];
+            let q = &contour[(i + 1) % contour.len()];
+            contour_normal[0] += (p.y - q.y) * (p.z + q.z);
+            contour_normal[1] += (p.z - q.z) * (p.x + q.x);
+            contour_normal[2] += (p.x - q.x) * (p.y + q.y);
+        }
+        for i in 0..3 {
+            normal[i] += contour_normal[i].abs();
+        }
+        start = end;
+    }
+    let (ax, ay, az) = (normal[0], normal[1], normal[2]);
+    points
+        .iter()
+        .map(|p| {
+            if az >= ax && az >= ay {
+                Point2::new(p.x, p.y)
+            } else if ay >= ax {
+                Point2::new(p.z, p.x)
+            } else {
+                Point2::new(p.y, p.z)
+            }
+        })
+        .collect()
+}
+
+pub fn signed_area(points: &[Point2<f32>]) -> f32 {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        let p = &points[i];
+        let q = &points[(i + 1) % points.len()];
+        area += p.x * q.y - q.x * p.y;
+    }
+    0.5 * area
+}
+
+fn cross(o: &Point2<f32>, a: &Point2<f32>, b: &Point2<f32>) -> f32 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+fn in_triangle(p: &Point2<f32>, a: &Point2<f32>, b: &Point2<f32>, c: &Point2<f32>) -> bool {
+    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+// Triangulates a simple, possibly concave, polygon by ear clipping. Returns a
+// list of triangles as consecutive triples of indices into points.
+pub fn triangulate(points: &[Point2<f32>]) -> Vec<u32> {
+    let n = points.len();
+    let mut triangles = Vec::with_capacity(3 * n.saturating_sub(2));
+    if n < 3 {
+        return triangles;
+    }
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    if signed_area(points) < 0.0 {
+        remaining.reverse();
+    }
+
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let mut ear = None;
+        for i in 0..m {
+            let prev = remaining[(i + m - 1) % m];
+            let curr = remaining[i];
+            let next = remaining[(i + 1) % m];
+            let (a, b, c) = (&points[prev], &points[curr], &points[next]);
+            let turn = cross(a, b, c);
+            if turn == 0.0 {
+                // collinear vertices add no area and can simply be dropped
+                ear = Some((i, false));
+                break;
+            }
+            if turn < 0.0 {
+                continue;
+            }
+            let blocked = remaining.iter().any(|&j| {
+                let p = &points[j];
+                j != prev && j != curr && j != next && p != a && p != b && p != c
+                    && in_triangle(p, a, b, c)
+            });
+            if !blocked {
+                ear = Some((i, true));
+                break;
+            }
+        }
+
+        // A self-intersecting polygon may have no ears left so clip the first
+        // vertex anyway rather than give up on the remainder
+        let (i, emit) = ear.unwrap_or((0, true));
+        if emit {
+            triangles.push(remaining[(i + m - 1) % m] as u32);
+            triangles.push(remaining[i] as u32);
+            triangles.push(remaining[(i + 1) % m] as u32);
+        }
+        remaining.remove(i);
+    }
+    triangles.extend(remaining.iter().map(|&i| i as u32));
+
+    triangles
+}
+
+// A tessellated vertex lying a fraction t of the way along the polygon edge
+// between the input points from and to. Attributes such as position and
+// color can be interpolated along that edge.
+#[derive(Clone, Copy, Debug)]
+pub struct EdgePoint {
+    pub from: usize,
+    pub to: usize,
+    pub t: f32,
+}
+
+struct Edge {
+    from: usize,
+    to: usize,
+    y_min: f32,
+    y_max: f32,
+    winding: i32,
+}
+
+impl Edge {
+    fn t_at(&self, points: &[Point2<f32>], y: f32) -> f32 {
+        let (a, b) = (&points[self.from], &points[self.to]);
+        (y - a.y) / (b.y - a.y)
+    }
+
+    fn x_at(&self, points: &[Point2<f32>], y: f32) -> f32 {
+        let (a, b) = (&points[self.from], &points[self.to]);
+        a.x + (b.x - a.x) * self.t_at(points, y)
+    }
+}
+
+fn edge_intersection_y(points: &[Point2<f32>], e: &Edge, f: &Edge) -> Option<f32> {
+    let (p, r) = (points[e.from], points[e.to] - points[e.from]);
+    let (q, s) = (points[f.from], points[f.to] - points[f.from]);
+    let denominator = r.x * s.y - r.y * s.x;
+    if denominator == 0.0 {
+        return None;
+    }
+    let qp = q - p;
+    let t = (qp.x * s.y - qp.y * s.x) / denominator;
+    let u = (qp.x * r.y - qp.y * r.x) / denominator;
+    if t <= 0.0 || t >= 1.0 || u <= 0.0 || u >= 1.0 {
+        return None;
+    }
+    let y = p.y + t * r.y;
+    if y > e.y_min && y < e.y_max && y > f.y_min && y < f.y_max {
+        Some(y)
+    } else {
+        None
+    }
+}
+
+// Tessellates any set of closed contours, including holes and
+// self-intersections, into triangles covering the area that is inside
+// according to the winding rule. contour_ends holds the exclusive end index of
+// each contour in points.
+//
+// The plane is cut into horizontal slabs at every vertex and edge
+// intersection so that no edges cross within a slab. Within each slab the
+// edges are ordered left to right and a trapezoid is emitted for each span
+// with an inside winding number. Returns the tessellated vertices and a list
+// of triangles as consecutive triples of indices into them.
+pub fn tessellate(
+    points: &[Point2<f32>],
+    contour_ends: &[usize],
+    rule: WindingRule,
+) -> (Vec<EdgePoint>, Vec<u32>) {
+    let mut edges = Vec::with_capacity(points.len());
+    let mut ys = Vec::with_capacity(points.len());
+    let mut start = 0;
+    for &end in contour_ends {
+        for from in start..end {
+            let to = if from + 1 == end { start } else { from + 1 };
+            let (a, b) = (&points[from], &points[to]);
+            ys.push(a.y);
+            // horizontal edges never cross a slab so cannot affect the winding
+            if a.y == b.y {
+                continue;
+            }
+            edges.push(Edge {
+                from,
+                to,
+                y_min: a.y.min(b.y),
+                y_max: a.y.max(b.y),
+                winding: if b.y > a.y { 1 } else { -1 },
+            });
+        }
+        start = end;
+    }
+
+    edges.sort_by(|e, f| e.y_min.partial_cmp(&f.y_min).unwrap_or(Ordering::Equal));
+    for i in 0..edges.len() {
+        for j in i + 1..edges.len() {
+            if edges[j].y_min >= edges[i].y_max {
+                break;
+            }
+            if let Some(y) = edge_intersection_y(points, &edges[i], &edges[j]) {
+                ys.push(y);
+            }
+        }
+    }
+    ys.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    ys.dedup();
+
+    let mut vertices = Vec::new();
+    let mut triangles = Vec::new();
+    {
+        let mut vertex_indices: HashMap<(usize, usize), u32> = HashMap::new();
+        let mut vertex_at = |edge: &Edge, edge_index: usize, y_index: usize| -> u32 {
+            let n_vertices = vertices.len() as u32;
+            let index = *vertex_indices
+                .entry((edge_index, y_index))
+                .or_insert(n_vertices);
+            if index == n_vertices {
+                vertices.push(EdgePoint {
+                    from: edge.from,
+                    to: edge.to,
+                    t: edge.t_at(points, ys[y_index]),
+                });
+            }
+            index
+        };
+
+        let mut active: Vec<(f32, usize)> = Vec::new();
+        let mut first_edge = 0;
+        for k in 1..ys.len() {
+            let (y0, y1) = (ys[k - 1], ys[k]);
+            let y_mid = 0.5 * (y0 + y1);
+            while first_edge < edges.len() && edges[first_edge].y_max <= y0 {
+                first_edge += 1;
+            }
+
+            active.clear();
+            for i in first_edge..edges.len() {
+                let edge = &edges[i];
+                if edge.y_min > y0 {
+                    break;
+                }
+                if edge.y_max >= y1 {
+                    active.push((edge.x_at(points, y_mid), i));
+                }
+            }
+            active.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+            let mut winding = 0;
+            let mut left = 0;
+            for &(_, i) in &active {
+                let was_inside = rule.is_inside(winding);
+                winding += edges[i].winding;
+                let is_inside = rule.is_inside(winding);
+                if !was_inside && is_inside {
+                    left = i;
+                } else if was_inside && !is_inside {
+                    let l0 = vertex_at(&edges[left], left, k - 1);
+                    let r0 = vertex_at(&edges[i], i, k - 1);
+                    let l1 = vertex_at(&edges[left], left, k);
+                    let r1 = vertex_at(&edges[i], i, k);
+                    triangles.extend_from_slice(&[l0, r0, l1, r0, r1, l1]);
+                }
+            }
+        }
+    }
+
+    (vertices, triangles)
+}
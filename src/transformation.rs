@@ -22,22 +22,80 @@
  * SOFTWARE.
  */
 
+use mesh::normal_transformation;
+use sketch::Sketch;
+
 use na::{Matrix4, Rotation3, Transform3, Translation, Vector3};
 
-use std::sync::{Mutex, MutexGuard};
+use std::sync::Mutex;
 
 lazy_static! {
     static ref TRANSFORMATION_STACK: Mutex<Vec<Transform3<f32>>> = Mutex::new(vec![Transform3::identity()]);
+    // the base transformation and the normal transformation of it combined
+    // with the top of the matrix stack, until the top is rotated, scaled or
+    // popped
+    static ref NORMAL_TRANSFORMATION: Mutex<Option<(Transform3<f32>, Matrix4<f32>)>> = Mutex::new(None);
 }
 
 pub fn reset() {
-    let mut transformation_stack = TRANSFORMATION_STACK.lock().unwrap();
-    transformation_stack.truncate(0);
-    transformation_stack.push(Transform3::identity());
+    {
+        let mut transformation_stack = TRANSFORMATION_STACK.lock().unwrap();
+        transformation_stack.truncate(0);
+        transformation_stack.push(Transform3::identity());
+    }
+    invalidate_normal_transformation();
+}
+
+// Returns a copy of the matrix stack, as changes to it must go through the
+// functions here to keep the normal transformation up to date
+pub fn get_transformations() -> Vec<Transform3<f32>> {
+    TRANSFORMATION_STACK.lock().unwrap().clone()
+}
+
+// Combines the sketch's base transformation with the top of the matrix stack
+pub fn get_transformation(base: &Transform3<f32>) -> Transform3<f32> {
+    let mut transform = *base;
+    if let Some(transformation) = TRANSFORMATION_STACK.lock().unwrap().last() {
+        transform *= transformation;
+    }
+    transform
+}
+
+// Returns the normal transformation of get_transformation() for the sketch's
+// base transformation, or None if nothing drawn now is lit or coloured by its
+// normals, in which case normals can be left facing +z
+pub fn get_normal_transformation(sketch: &Sketch) -> Option<Matrix4<f32>> {
+    if !sketch.uses_normals() {
+        return None;
+    }
+    let mut cached = NORMAL_TRANSFORMATION.lock().unwrap();
+    if let Some((base, normal_transform)) = *cached {
+        if base == sketch.transformation {
+            return Some(normal_transform);
+        }
+    }
+    let normal_transform = normal_transformation(&get_transformation(&sketch.transformation));
+    *cached = Some((sketch.transformation, normal_transform));
+    Some(normal_transform)
+}
+
+fn invalidate_normal_transformation() {
+    *NORMAL_TRANSFORMATION.lock().unwrap() = None;
 }
 
-pub fn get_transformations<'a>() -> MutexGuard<'a, Vec<Transform3<f32>>> {
-    TRANSFORMATION_STACK.lock().unwrap()
+// Returns the transformations from model space into screen space, which is in
+// pixels with the origin at the centre of the window and normalized device
+// depth, and from screen space back into view space. Both include the
+// projection's perspective divide.
+pub fn get_screen_transformations(sketch: &Sketch) -> (Transform3<f32>, Transform3<f32>) {
+    let half_width = 0.5 * sketch.width as f32;
+    let half_height = 0.5 * sketch.height as f32;
+    let to_pixels = Matrix4::new_nonuniform_scaling(&Vector3::new(half_width, half_height, 1.0));
+    let view_to_screen = Transform3::from_matrix_unchecked(to_pixels) * sketch.projection;
+    (
+        view_to_screen * get_transformation(&sketch.transformation),
+        view_to_screen.try_inverse().unwrap_or_else(Transform3::identity),
+    )
 }
 
 pub fn push_matrix() {
@@ -53,12 +111,14 @@ pub fn push_matrix() {
 
 pub fn pop_matrix() {
     TRANSFORMATION_STACK.lock().unwrap().pop();
+    invalidate_normal_transformation();
 }
 
 pub fn translate(translation: &Vector3<f32>) {
     if let Some(transformation) = TRANSFORMATION_STACK.lock().unwrap().last_mut() {
         *transformation = Translation::from_vector(*translation) * *transformation;
     }
+    // translations leave normals as they are
 }
 
 pub fn rotate(angle: f32) {
@@ -69,18 +129,21 @@ pub fn rotate_x(angle: f32) {
     if let Some(transformation) = TRANSFORMATION_STACK.lock().unwrap().last_mut() {
         *transformation = Rotation3::from_scaled_axis(&Vector3::x() * angle) * *transformation;
     }
+    invalidate_normal_transformation();
 }
 
 pub fn rotate_y(angle: f32) {
     if let Some(transformation) = TRANSFORMATION_STACK.lock().unwrap().last_mut() {
         *transformation = Rotation3::from_scaled_axis(&Vector3::y() * angle) * *transformation;
     }
+    invalidate_normal_transformation();
 }
 
 pub fn rotate_z(angle: f32) {
     if let Some(transformation) = TRANSFORMATION_STACK.lock().unwrap().last_mut() {
         *transformation = Rotation3::from_scaled_axis(&Vector3::z() * angle) * *transformation;
     }
+    invalidate_normal_transformation();
 }
 
 pub fn scale(factors: &Vector3<f32>) {
@@ -89,4 +152,5 @@ pub fn scale(factors: &Vector3<f32>) {
             Matrix4::new_nonuniform_scaling(factors)
         ) * *transformation;
     }
+    invalidate_normal_transformation();
 }
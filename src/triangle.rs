@@ -22,27 +22,36 @@
  * SOFTWARE.
  */
 
+use color::{get_fill, get_stroke};
+use glapp::VBO_STRIDE_N;
+use mesh::transform_normal;
 use shape;
 use shape::*;
 use sketch;
-use transformation::get_transformations;
+use stroke::polyline;
+use transformation::{get_normal_transformation, get_transformation};
 use utils::*;
 
-use na::Point3;
+use na::{Point3, Vector3};
 
 pub fn triangle(p1: Point3<f32>, p2: Point3<f32>, p3: Point3<f32>) {
-    Triangle::new(p1, p2, p3).draw();
+    if get_fill().w > 0.0 {
+        Triangle::new(p1, p2, p3).draw();
+    }
+    if get_stroke().w > 0.0 {
+        polyline(&[p1, p2, p3], true);
+    }
 }
 
 pub struct Triangle {
-    vertex_data: [f32; 9 * 3],
+    vertex_data: [f32; VBO_STRIDE_N * 3],
     index_data: [u32; 3],
 }
 
 impl Triangle {
     pub fn new(p1: Point3<f32>, p2: Point3<f32>, p3: Point3<f32>) -> Triangle {
         let mut triangle = Triangle {
-            vertex_data: [0.0; 9 * 3],
+            vertex_data: [0.0; VBO_STRIDE_N * 3],
             index_data: [0, 1, 2],
         };
 
@@ -61,20 +70,23 @@ impl Triangle {
         }
 
         let sketch = sketch::get_sketch();
-        let mut transform = sketch.transformation;
-        {
-            let transformations = get_transformations();
-            if let Some(transformation) = transformations.last() {
-                transform *= transformation;
-            }
-        }
+        let transform = get_transformation(&sketch.transformation);
         let color = &sketch.fill;
+        let mut normal = (points[1] - points[0]).cross(&(points[2] - points[0]));
+        if normal.norm_squared() == 0.0 {
+            normal = Vector3::z();
+        }
+        let normal = match get_normal_transformation(&sketch) {
+            Some(normal_transform) => transform_normal(&normal_transform, &normal),
+            None => Vector3::z(),
+        };
         for i in 0..points.len() {
-            assign_vertex(
+            assign_vertex_normal(
                 &(transform * points[i]),
+                &normal,
                 &uvs[i * 2..],
                 color,
-                &mut triangle.vertex_data[i * 9..],
+                &mut triangle.vertex_data[i * VBO_STRIDE_N..],
             );
         }
 
@@ -101,4 +113,7 @@ impl Shape for Triangle {
     fn is_stroke(&self) -> bool {
         false
     }
+    fn uv_edges(&self) -> UvEdges {
+        UvEdges::None
+    }
 }